@@ -0,0 +1,466 @@
+use bevy::{prelude::*, ui_widgets::observe};
+use std::collections::HashSet;
+
+use crate::{
+    AppState,
+    data::{AutomatonVariant, GameData, UnlockRequirement},
+    definitions::{DefinitionFile, Definitions, DefinitionsChanged},
+    economy::ProgressMessages,
+    interface::{
+        CONTROL_ACCENT, CONTROL_MUTED_TEXT, CONTROL_TEXT, CONTROL_TEXT_FONT_SIZE,
+        CONTROL_TITLE_FONT_SIZE, FONT_PATH, HudMenu, HudPanel, PANEL_BACKGROUND, PANEL_BORDER,
+        control_text, dialog_button, toggle_hud_panel,
+    },
+};
+
+const TOAST_DURATION: f32 = 5.0;
+const ENTRY_FONT_SIZE: f32 = 14.0;
+
+/// One entry of `assets/data/achievements.json`. Hidden achievements keep
+/// their name and description secret until unlocked.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub hidden: bool,
+    pub condition: UnlockRequirement,
+}
+
+#[derive(Asset, TypePath, Debug, Clone, serde::Deserialize)]
+pub struct AchievementDefinitions {
+    pub achievements: Vec<AchievementDefinition>,
+}
+
+/// Achievements have no source of their own, so their conditions are checked
+/// as if for the portal. Validation rejects conditions that would notice.
+const ACHIEVEMENT_SOURCE: AutomatonVariant = AutomatonVariant::Portal;
+
+/// Whether `requirement` refers to the source it belongs to, at any depth.
+fn refers_to_own_source(requirement: &UnlockRequirement) -> bool {
+    match requirement {
+        UnlockRequirement::FirstPurchaseCost => true,
+        UnlockRequirement::All(requirements) | UnlockRequirement::Any(requirements) => {
+            requirements.iter().any(refers_to_own_source)
+        }
+        _ => false,
+    }
+}
+
+impl DefinitionFile for AchievementDefinitions {
+    const PATH: &'static str = "data/achievements.json";
+    const BUILTIN: &'static str = include_str!("../assets/data/achievements.json");
+
+    /// Checks that ids are unique and that conditions can be met.
+    fn validate(&self, _asset_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for achievement in &self.achievements {
+            if !ids.insert(achievement.id.as_str()) {
+                return Err(format!(
+                    "achievement {} is defined more than once",
+                    achievement.id
+                ));
+            }
+            if refers_to_own_source(&achievement.condition) {
+                return Err(format!(
+                    "achievement {} has no source to check FirstPurchaseCost against",
+                    achievement.id
+                ));
+            }
+            if let Some(variant) = achievement
+                .condition
+                .required_variants()
+                .into_iter()
+                .find(|variant| !variant.is_automaton())
+            {
+                return Err(format!(
+                    "achievement {} needs {variant:?}, which is not an automaton",
+                    achievement.id
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, definitions: &mut Definitions) {
+        definitions.achievements = self.achievements.as_slice().into();
+    }
+}
+
+/// Written once when an achievement unlocks.
+#[derive(Message, Debug, Clone)]
+pub struct AchievementUnlocked {
+    pub achievement: AchievementDefinition,
+}
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<AchievementUnlocked>();
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            (
+                spawn_achievement_ui.after(crate::interface::setup),
+                check_achievements_on_load,
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                check_achievements,
+                show_achievement_toasts,
+                expire_achievement_toasts,
+                rebuild_achievement_gallery,
+                update_achievement_entries,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Achievements whose conditions are met but are not yet recorded.
+fn newly_met(data: &GameData) -> impl Iterator<Item = &AchievementDefinition> {
+    data.definitions()
+        .achievements
+        .iter()
+        .filter(|achievement| !data.has_achievement(&achievement.id))
+        .filter(|achievement| achievement.condition.is_met(ACHIEVEMENT_SOURCE, data))
+}
+
+/// Checks achievements again whenever progress is announced or their
+/// definitions change; nothing else can meet a condition.
+fn check_achievements(
+    mut progress: ProgressMessages,
+    mut changes: MessageReader<DefinitionsChanged<AchievementDefinitions>>,
+    mut data: ResMut<GameData>,
+    mut unlocked: MessageWriter<AchievementUnlocked>,
+) {
+    let edited = changes.read().count() > 0;
+    if progress.any() || edited {
+        record_met_achievements(&mut data, &mut unlocked);
+    }
+}
+
+/// Catches achievements a newly loaded profile already meets.
+fn check_achievements_on_load(
+    mut data: ResMut<GameData>,
+    mut unlocked: MessageWriter<AchievementUnlocked>,
+) {
+    record_met_achievements(&mut data, &mut unlocked);
+}
+
+fn record_met_achievements(data: &mut GameData, unlocked: &mut MessageWriter<AchievementUnlocked>) {
+    let met: Vec<_> = newly_met(data).cloned().collect();
+    for achievement in met {
+        if data.unlock_achievement(&achievement.id) {
+            unlocked.write(AchievementUnlocked { achievement });
+        }
+    }
+}
+
+#[derive(Component)]
+struct AchievementToasts;
+
+#[derive(Component)]
+struct AchievementToast(Timer);
+
+#[derive(Component)]
+struct AchievementPanel;
+
+#[derive(Component)]
+struct AchievementGallery;
+
+#[derive(Component)]
+struct AchievementCount;
+
+#[derive(Component)]
+struct AchievementEntry(AchievementDefinition);
+
+#[derive(Component)]
+struct AchievementName;
+
+#[derive(Component)]
+struct AchievementDescription;
+
+fn spawn_achievement_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    menu: Single<Entity, With<HudMenu>>,
+) {
+    let font_handle = asset_server.load(FONT_PATH);
+
+    commands.entity(*menu).with_child(dialog_button(
+        &font_handle,
+        "Achievements",
+        observe(toggle_hud_panel::<AchievementPanel>),
+    ));
+
+    commands.spawn((
+        AchievementToasts,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(12),
+            left: percent(50),
+            margin: UiRect::left(px(-160)),
+            width: px(320),
+            flex_direction: FlexDirection::ColumnReverse,
+            row_gap: px(6),
+            ..default()
+        },
+        GlobalZIndex(10),
+        Pickable::IGNORE,
+    ));
+
+    commands
+        .spawn((
+            AchievementPanel,
+            HudPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                right: px(12),
+                top: px(56),
+                width: px(420),
+                padding: UiRect::all(px(14)),
+                border: UiRect::all(px(1)),
+                border_radius: BorderRadius::all(px(6)),
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                row_gap: px(8),
+                ..default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            BorderColor::all(PANEL_BORDER),
+            GlobalZIndex(5),
+        ))
+        .with_children(|panel| {
+            panel.spawn(control_text(
+                &font_handle,
+                "Achievements",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            panel.spawn((
+                AchievementCount,
+                control_text(&font_handle, "", CONTROL_TEXT_FONT_SIZE, CONTROL_MUTED_TEXT),
+            ));
+            panel.spawn((
+                AchievementGallery,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: px(4),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn show_achievement_toasts(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut unlocked: MessageReader<AchievementUnlocked>,
+    toasts: Query<Entity, With<AchievementToasts>>,
+) {
+    for message in unlocked.read() {
+        let font_handle = asset_server.load(FONT_PATH);
+        for container in &toasts {
+            commands.entity(container).with_child((
+                AchievementToast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+                Node {
+                    padding: UiRect::all(px(10)),
+                    border: UiRect::all(px(1)),
+                    border_radius: BorderRadius::all(px(6)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                BackgroundColor(PANEL_BACKGROUND),
+                BorderColor::all(CONTROL_ACCENT),
+                Pickable::IGNORE,
+                children![
+                    control_text(
+                        &font_handle,
+                        "Achievement unlocked",
+                        ENTRY_FONT_SIZE,
+                        CONTROL_MUTED_TEXT,
+                    ),
+                    control_text(
+                        &font_handle,
+                        message.achievement.name.clone(),
+                        CONTROL_TEXT_FONT_SIZE,
+                        CONTROL_TEXT,
+                    ),
+                ],
+            ));
+        }
+    }
+}
+
+fn expire_achievement_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut AchievementToast)>,
+) {
+    for (entity, mut toast) in &mut toasts {
+        if toast.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Lists every achievement, rebuilt when the definitions change.
+fn rebuild_achievement_gallery(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    definitions: Res<Definitions>,
+    mut changes: MessageReader<DefinitionsChanged<AchievementDefinitions>>,
+    galleries: Query<Entity, With<AchievementGallery>>,
+    spawned: Query<(), Added<AchievementGallery>>,
+) {
+    if changes.read().count() == 0 && spawned.is_empty() {
+        return;
+    }
+
+    let font_handle = asset_server.load(FONT_PATH);
+    for gallery in &galleries {
+        commands.entity(gallery).despawn_children();
+        commands.entity(gallery).with_children(|gallery| {
+            for achievement in definitions.achievements.iter() {
+                gallery.spawn((
+                    AchievementEntry(achievement.clone()),
+                    Node {
+                        padding: UiRect::axes(px(8), px(4)),
+                        border: UiRect::left(px(3)),
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    },
+                    BorderColor::all(PANEL_BORDER),
+                    children![
+                        (
+                            AchievementName,
+                            control_text(&font_handle, "", ENTRY_FONT_SIZE, CONTROL_MUTED_TEXT),
+                        ),
+                        (
+                            AchievementDescription,
+                            control_text(&font_handle, "", ENTRY_FONT_SIZE, CONTROL_MUTED_TEXT),
+                        ),
+                    ],
+                ));
+            }
+        });
+    }
+}
+
+type AchievementTextQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Text,
+        &'static mut TextColor,
+        Has<AchievementName>,
+    ),
+    Or<(With<AchievementName>, With<AchievementDescription>)>,
+>;
+
+type AchievementCountQuery<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Text,
+    (
+        With<AchievementCount>,
+        Without<AchievementName>,
+        Without<AchievementDescription>,
+    ),
+>;
+
+fn update_achievement_entries(
+    data: Res<GameData>,
+    panels: Query<&Node, With<AchievementPanel>>,
+    mut entries: Query<(&AchievementEntry, &mut BorderColor, &Children)>,
+    mut texts: AchievementTextQuery<'_, '_>,
+    mut counts: AchievementCountQuery<'_, '_>,
+) {
+    if panels.iter().all(|node| node.display == Display::None) {
+        return;
+    }
+
+    let all = &data.definitions().achievements;
+    let unlocked_count = all.iter().filter(|a| data.has_achievement(&a.id)).count();
+    for mut text in &mut counts {
+        text.0 = format!("{unlocked_count} / {} unlocked", all.len());
+    }
+
+    for (entry, mut border, children) in &mut entries {
+        let achievement = &entry.0;
+        let unlocked = data.has_achievement(&achievement.id);
+        let (name, description) = if unlocked || !achievement.hidden {
+            (achievement.name.as_str(), achievement.description.as_str())
+        } else {
+            ("???", "Hidden achievement")
+        };
+        let color = if unlocked {
+            CONTROL_TEXT
+        } else {
+            CONTROL_MUTED_TEXT
+        };
+        border.set_all(if unlocked {
+            CONTROL_ACCENT
+        } else {
+            PANEL_BORDER
+        });
+
+        for child in children.iter() {
+            if let Ok((mut text, mut text_color, is_name)) = texts.get_mut(child) {
+                let shown = if is_name { name } else { description };
+                if text.0 != shown {
+                    text.0 = shown.to_string();
+                }
+                text_color.0 = color;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions;
+
+    #[test]
+    fn builtin_achievements_are_valid() {
+        let definitions: AchievementDefinitions =
+            definitions::parse(AchievementDefinitions::BUILTIN, &|_| true).unwrap();
+
+        assert!(definitions.achievements.iter().any(|a| a.hidden));
+    }
+
+    #[test]
+    fn conditions_on_the_portal_or_a_purchase_cost_are_rejected() {
+        let on_portal = definitions::parse_edited::<AchievementDefinitions>(|achievements| {
+            achievements[0]["condition"] =
+                serde_json::json!({ "PreviousAutomaton": { "variant": "Portal", "quantity": 1 } });
+        });
+        let on_cost = definitions::parse_edited::<AchievementDefinitions>(|achievements| {
+            achievements[0]["condition"] =
+                serde_json::json!({ "Any": [{ "WakeUps": 1 }, "FirstPurchaseCost"] });
+        });
+
+        assert!(on_portal.is_err());
+        assert!(on_cost.is_err());
+    }
+
+    #[test]
+    fn achievements_unlock_once_their_condition_is_met() {
+        let mut data = GameData::default();
+        let ids = |data: &GameData| newly_met(data).map(|a| a.id.clone()).collect::<Vec<_>>();
+        assert!(ids(&data).is_empty());
+
+        data.click_portal(1.0);
+        assert_eq!(ids(&data), ["first_click"]);
+
+        assert!(data.unlock_achievement("first_click"));
+        assert!(!data.unlock_achievement("first_click"));
+        assert!(ids(&data).is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 
-use crate::rand;
+use crate::{AppState, rand::RandomSource};
 
 pub struct AudioPlugin;
 
@@ -42,9 +42,11 @@ impl Plugin for AudioPlugin {
         // AudioState will be initialized in start_background_audio after GameData is available
         app.add_plugins(bevy_kira_audio::AudioPlugin);
         app.add_audio_channel::<InteractionChannel>();
-        app.add_systems(Startup, start_background_audio);
-        app.add_systems(Update, play_next_track_on_end);
-        app.add_systems(Update, update_player_volume);
+        app.add_systems(OnEnter(AppState::Playing), start_background_audio);
+        app.add_systems(
+            Update,
+            (play_next_track_on_end, update_player_volume).run_if(in_state(AppState::Playing)),
+        );
     }
 }
 
@@ -54,11 +56,12 @@ pub fn start_background_audio(
     audio: Res<Audio>,
     interaction: Res<AudioChannel<InteractionChannel>>,
     game_data: Res<crate::data::GameData>,
+    mut random: ResMut<RandomSource>,
 ) {
     let pickup_handle = asset_server.load(PICKUP_AUDIO);
     interaction.set_volume(-55.0);
 
-    let current_track = rand::random_range(0..BACKGROUND_AUDIO.len());
+    let current_track = random.visuals().random_range(0..BACKGROUND_AUDIO.len());
     let current_track_handle = Some(
         audio
             .play(asset_server.load(BACKGROUND_AUDIO[current_track]))
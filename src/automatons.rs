@@ -6,12 +6,17 @@ use bevy::{
 use bevy_kira_audio::prelude::*;
 
 use crate::{
-    audio,
-    data::{AutomatonVariant, automaton_definitions},
+    AppState, audio,
+    data::AutomatonVariant,
+    definitions::{Definitions, DefinitionsChanged, SourceDefinitions},
     interface::{InterfaceState, set_hovered_automaton},
-    rand,
+    rand::{RandomSource, Rng},
 };
 
+/// Most models shown per variant. Income doesn't depend on these entities,
+/// so past this the ring stands for the rest and frame cost stays flat.
+const MAX_VISUALS_PER_VARIANT: u64 = 24;
+
 pub struct AutomatonsPlugin;
 
 impl Plugin for AutomatonsPlugin {
@@ -24,30 +29,29 @@ impl Plugin for AutomatonsPlugin {
                 movement,
                 update_automatons,
                 make_automaton_meshes_unpickable,
-            ),
+            )
+                .run_if(in_state(AppState::Playing)),
         );
+        app.add_systems(Update, refresh_from_definitions);
     }
 }
 
 #[derive(Component)]
-pub struct PurchaseRing;
+pub struct PurchaseRing(AutomatonVariant);
 
+/// The visual for one owned automaton. Stats are read from its definition on
+/// every use so edited definitions take effect immediately.
 #[derive(Component)]
 pub struct Automaton {
     source: AutomatonVariant,
-    currency_per_tick: u64,
-    cooldown: f32,
     time_left: f32,
 }
 
 impl Automaton {
-    fn new(source: AutomatonVariant) -> Self {
-        let stats = source.stats();
+    fn new(source: AutomatonVariant, cooldown: f32, rng: &mut Rng) -> Self {
         Self {
             source,
-            currency_per_tick: stats.currency_per_tick,
-            cooldown: stats.cooldown,
-            time_left: random_time_left(stats.cooldown),
+            time_left: random_time_left(cooldown, rng),
         }
     }
 }
@@ -55,6 +59,7 @@ impl Automaton {
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    definitions: Res<Definitions>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
@@ -73,7 +78,7 @@ fn setup(
         ..default()
     });
 
-    for definition in automaton_definitions() {
+    for definition in definitions.automatons() {
         let stats = &definition.stats;
         let model_path = definition
             .model_path()
@@ -85,12 +90,9 @@ fn setup(
 
         commands
             .spawn((
-                PurchaseRing,
+                PurchaseRing(definition.variant),
                 Name::new(ring_name),
-                Mesh3d(meshes.add(Torus {
-                    minor_radius: stats.scale,
-                    major_radius: stats.distance_from_origin,
-                })),
+                Mesh3d(meshes.add(ring_mesh(stats.scale, stats.distance_from_origin))),
                 MeshMaterial3d(transparent_mat.clone()),
                 NotShadowCaster,
                 NotShadowReceiver,
@@ -131,36 +133,39 @@ fn update_based_on_owned(
     game_data: Res<crate::data::GameData>,
     asset_server: Res<AssetServer>,
     mut automatons: Query<(Entity, &Automaton, &mut Transform)>,
+    mut random: ResMut<RandomSource>,
 ) {
-    for definition in automaton_definitions() {
+    for definition in game_data.definitions().automatons() {
         let variant = definition.variant;
-        let quantity_owned = game_data.get_quantity_owned_by_source(variant);
+        let quantity_shown = game_data
+            .get_quantity_owned_by_source(variant)
+            .min(MAX_VISUALS_PER_VARIANT);
         let current_count = automatons
             .iter()
             .filter(|(_, automaton, _)| automaton.source == variant)
             .count() as u64;
 
-        if quantity_owned == current_count {
+        if quantity_shown == current_count {
             continue;
         }
 
         let stats = &definition.stats;
 
-        if quantity_owned > current_count {
+        if quantity_shown > current_count {
             let model_path = definition
                 .model_path()
                 .expect("automaton definition should have a model path");
-            for new_index in current_count..quantity_owned {
+            for new_index in current_count..quantity_shown {
                 let scene: Handle<Scene> =
                     asset_server.load(GltfAssetLabel::Scene(0).from_asset(model_path.clone()));
 
                 commands.spawn((
-                    Name::new(definition.display_name),
+                    Name::new(definition.display_name.clone()),
                     SceneRoot(scene),
-                    Automaton::new(variant),
+                    Automaton::new(variant, stats.cooldown, random.visuals()),
                     circle_transform(
                         new_index,
-                        quantity_owned,
+                        quantity_shown,
                         stats.distance_from_origin,
                         stats.scale,
                     ),
@@ -174,13 +179,13 @@ fn update_based_on_owned(
             {
                 *transform = circle_transform(
                     i as u64,
-                    quantity_owned,
+                    quantity_shown,
                     stats.distance_from_origin,
                     stats.scale,
                 );
             }
         } else {
-            let mut to_remove = (current_count - quantity_owned) as usize;
+            let mut to_remove = (current_count - quantity_shown) as usize;
             let mut despawned: Vec<Entity> = Vec::with_capacity(to_remove);
             for (entity, automaton, _) in automatons.iter() {
                 if to_remove == 0 {
@@ -202,7 +207,7 @@ fn update_based_on_owned(
             {
                 *transform = circle_transform(
                     i as u64,
-                    quantity_owned,
+                    quantity_shown,
                     stats.distance_from_origin,
                     stats.scale,
                 );
@@ -211,10 +216,12 @@ fn update_based_on_owned(
     }
 }
 
+/// Sends an orb to the portal each time an automaton's cooldown runs out.
+/// Purely visual: income is credited per variant by `production`.
 pub fn update_automatons(
     mut automatons: Query<(&mut Automaton, &Transform), Without<AutomatonOrb>>,
     time: Res<Time>,
-    mut data: ResMut<crate::data::GameData>,
+    data: Res<crate::data::GameData>,
     mut orbs: Query<(&mut Transform, &mut AutomatonOrb, &mut Visibility)>,
     interaction: Res<AudioChannel<crate::audio::InteractionChannel>>,
     audio_state: Res<crate::audio::AudioState>,
@@ -223,11 +230,9 @@ pub fn update_automatons(
         if automaton.time_left >= 0.0 {
             automaton.time_left -= time.delta_secs();
         } else {
-            data.add_income(automaton.source, automaton.currency_per_tick);
-
             audio::play_pickup_sound(&interaction, &audio_state);
 
-            automaton.time_left = automaton.cooldown;
+            automaton.time_left = data.cooldown(automaton.source);
             for (mut orb_transform, mut orb, _) in orbs.iter_mut() {
                 if orb_transform.translation.distance(Vec3::ZERO) <= 0.25 {
                     orb.start = entity_transform.translation;
@@ -256,17 +261,21 @@ pub fn update_automatons(
     }
 }
 
-fn movement(mut query: Query<(&mut Transform, &Automaton)>, time: Res<Time>) {
+fn movement(
+    mut query: Query<(&mut Transform, &Automaton)>,
+    time: Res<Time>,
+    data: Res<crate::data::GameData>,
+) {
     let nudge_amount = 0.1;
     let nudge_recovery_duration = 0.5;
 
     for (mut transform, automaton) in query.iter_mut() {
-        let stats = automaton.source.stats();
+        let stats = &data.definitions().source(automaton.source).stats;
         let angle = stats.rotation * time.delta_secs();
         let rot = Quat::from_rotation_y(angle);
         let new_translation = rot * transform.translation;
 
-        let time_since_tick = automaton.cooldown - automaton.time_left;
+        let time_since_tick = data.cooldown(automaton.source) - automaton.time_left;
         let current_distance = if time_since_tick < nudge_recovery_duration {
             let t = time_since_tick / nudge_recovery_duration;
             stats.distance_from_origin - nudge_amount * (1.0 - t)
@@ -281,8 +290,36 @@ fn movement(mut query: Query<(&mut Transform, &Automaton)>, time: Res<Time>) {
     }
 }
 
-fn random_time_left(cooldown: f32) -> f32 {
-    rand::random_range(0.0..cooldown)
+fn ring_mesh(scale: f32, distance_from_origin: f32) -> Torus {
+    Torus {
+        minor_radius: scale,
+        major_radius: distance_from_origin,
+    }
+}
+
+/// Resizes purchase rings and automatons after the definitions are edited.
+/// Distances and rotation speeds are already read live by `movement`.
+fn refresh_from_definitions(
+    mut changes: MessageReader<DefinitionsChanged<SourceDefinitions>>,
+    definitions: Res<Definitions>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut rings: Query<(&PurchaseRing, &mut Mesh3d)>,
+    mut automatons: Query<(&Automaton, &mut Transform)>,
+) {
+    if changes.read().count() == 0 {
+        return;
+    }
+    for (ring, mut mesh) in &mut rings {
+        let stats = &definitions.source(ring.0).stats;
+        mesh.0 = meshes.add(ring_mesh(stats.scale, stats.distance_from_origin));
+    }
+    for (automaton, mut transform) in &mut automatons {
+        transform.scale = Vec3::splat(definitions.source(automaton.source).stats.scale);
+    }
+}
+
+fn random_time_left(cooldown: f32, rng: &mut Rng) -> f32 {
+    rng.random_range(0.0..cooldown)
 }
 
 fn circle_transform(index: u64, total: u64, distance_from_origin: f32, scale: f32) -> Transform {
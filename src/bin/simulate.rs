@@ -0,0 +1,214 @@
+//! Runs the economy headless for balancing the definitions under `assets/data`.
+//!
+//! ```text
+//! cargo run --bin simulate -- --hours 4 --strategy payback --format csv --out simulation
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use fever_dream_inc::{
+    data::GameData,
+    definitions::{self, DefinitionFile, Definitions, SourceDefinitions},
+    simulation::{
+        BestPayback, GreedyCheapest, Purchase, Scripted, Simulation, SimulationReport,
+        SimulationSettings, Strategy,
+    },
+};
+
+const USAGE: &str = "\
+usage: simulate [options]
+
+  --hours N          simulated hours (default 1)
+  --timestep S       seconds per step (default 0.1)
+  --clicks N         portal clicks per second (default 2)
+  --seed N           seed for crit rolls (default 1)
+  --strategy NAME    greedy, payback or script (default greedy)
+  --script PATH      JSON list of purchases for the script strategy, such as
+                     [{\"Source\": \"Hellmite\"}, {\"Upgrade\": \"barbed_claws\"}]
+  --sources PATH     source definitions to use instead of the built-in ones
+  --format FORMAT    json, printed to stdout, or csv (default json)
+  --out DIR          directory for the csv files (default simulation)";
+
+struct Options {
+    hours: f64,
+    settings: SimulationSettings,
+    strategy: String,
+    script: Option<PathBuf>,
+    sources: Option<PathBuf>,
+    format: String,
+    out: PathBuf,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        hours: 1.0,
+        settings: SimulationSettings::default(),
+        strategy: "greedy".to_string(),
+        script: None,
+        sources: None,
+        format: "json".to_string(),
+        out: PathBuf::from("simulation"),
+    };
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{flag} needs a value"))?;
+        let number = || {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite() && *number >= 0.0)
+                .ok_or_else(|| format!("{flag} needs a number, not {value}"))
+        };
+        match flag.as_str() {
+            "--hours" => options.hours = number()?,
+            "--timestep" => options.settings.timestep = number()?,
+            "--clicks" => options.settings.clicks_per_second = number()?,
+            "--seed" => {
+                options.settings.seed = value
+                    .parse()
+                    .map_err(|_| format!("--seed needs a whole number, not {value}"))?
+            }
+            "--strategy" => options.strategy = value,
+            "--script" => options.script = Some(PathBuf::from(value)),
+            "--sources" => options.sources = Some(PathBuf::from(value)),
+            "--format" => options.format = value,
+            "--out" => options.out = PathBuf::from(value),
+            _ => return Err(format!("unknown option {flag}")),
+        }
+    }
+    if options.settings.timestep <= 0.0 {
+        return Err("--timestep must be above zero".to_string());
+    }
+    Ok(options)
+}
+
+fn strategy(options: &Options, definitions: &Definitions) -> Result<Box<dyn Strategy>, String> {
+    match options.strategy.as_str() {
+        "greedy" => Ok(Box::new(GreedyCheapest)),
+        "payback" => Ok(Box::new(BestPayback)),
+        "script" => {
+            let path = options
+                .script
+                .as_ref()
+                .ok_or("the script strategy needs --script")?;
+            let contents = fs::read_to_string(path)
+                .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+            let steps: Vec<Purchase> = serde_json::from_str(&contents)
+                .map_err(|err| format!("could not parse {}: {err}", path.display()))?;
+            // An unknown upgrade could never be bought, stalling the script for the whole run.
+            for step in &steps {
+                if let Purchase::Upgrade(id) = step
+                    && definitions.upgrade(id).is_none()
+                {
+                    return Err(format!("{}: unknown upgrade {id}", path.display()));
+                }
+            }
+            Ok(Box::new(Scripted::new(steps)))
+        }
+        other => Err(format!("unknown strategy {other}")),
+    }
+}
+
+/// The built-in definitions, with the sources from `--sources` if given.
+fn definitions(options: &Options) -> Result<Definitions, String> {
+    let mut in_effect = Definitions::default();
+    if let Some(path) = &options.sources {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+        // Models only matter for rendering, so they are not checked.
+        let sources: SourceDefinitions = definitions::parse(&contents, &|_| true)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        sources.apply(&mut in_effect);
+    }
+    Ok(in_effect)
+}
+
+fn write_csv(report: &SimulationReport, out: &Path) -> Result<(), String> {
+    let mut unlocks = "variant,seconds\n".to_string();
+    for unlock in &report.unlocks {
+        let _ = writeln!(unlocks, "{:?},{:.1}", unlock.variant, unlock.seconds);
+    }
+    let mut currency = "seconds,currency,generated,rate_per_second\n".to_string();
+    for sample in &report.currency {
+        let _ = writeln!(
+            currency,
+            "{:.1},{},{},{}",
+            sample.seconds,
+            sample.currency.to_f64(),
+            sample.generated.to_f64(),
+            sample.rate_per_second
+        );
+    }
+    let mut purchases = "seconds,kind,target,cost\n".to_string();
+    for record in &report.purchases {
+        let (kind, target) = match &record.purchase {
+            Purchase::Source(variant) => ("Source", format!("{variant:?}")),
+            Purchase::LevelUp(variant) => ("LevelUp", format!("{variant:?}")),
+            Purchase::Upgrade(id) => ("Upgrade", id.clone()),
+        };
+        let _ = writeln!(
+            purchases,
+            "{:.1},{kind},{target},{}",
+            record.seconds,
+            record.cost.to_f64()
+        );
+    }
+
+    fs::create_dir_all(out).map_err(|err| format!("could not create {}: {err}", out.display()))?;
+    for (name, contents) in [
+        ("unlocks.csv", unlocks),
+        ("currency.csv", currency),
+        ("purchases.csv", purchases),
+    ] {
+        let path = out.join(name);
+        fs::write(&path, contents)
+            .map_err(|err| format!("could not write {}: {err}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let definitions = definitions(&options)?;
+    let mut strategy = strategy(&options, &definitions)?;
+    let mut data = GameData::default();
+    data.set_definitions(definitions);
+    let mut simulation = Simulation::new(data, options.settings.clone());
+    simulation.run(strategy.as_mut(), options.hours * 60.0 * 60.0);
+
+    match options.format.as_str() {
+        "json" => {
+            let json = serde_json::to_string_pretty(simulation.report())
+                .map_err(|err| format!("could not write the report: {err}"))?;
+            println!("{json}");
+            Ok(())
+        }
+        "csv" => write_csv(simulation.report(), &options.out),
+        other => Err(format!("unknown format {other}")),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let options = match parse_options(args.into_iter()) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("simulate: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
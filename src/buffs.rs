@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    data::GameData,
+    interface::{
+        CONTROL_ACCENT, CONTROL_TEXT, CONTROL_TRACK, FONT_PATH, PANEL_BACKGROUND, PANEL_BORDER,
+        control_text,
+    },
+};
+
+const BUFF_FONT_SIZE: f32 = 14.0;
+const BUFF_BAR_WIDTH: f32 = 220.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum BuffEffect {
+    /// Multiplies everything the sources produce.
+    Production,
+    /// Multiplies portal clicks only.
+    Clicks,
+}
+
+/// What happens when a buff is granted while one with the same name is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuffStacking {
+    /// Restart the existing buff's timer.
+    Refresh,
+    /// Add the new duration to the existing buff.
+    Extend,
+    /// Run alongside the existing buff, multiplying with it.
+    Stack,
+}
+
+/// A temporary multiplier. `remaining` counts down only while the game runs,
+/// so buffs are saved with the time they had left.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Buff {
+    pub name: String,
+    pub effect: BuffEffect,
+    pub multiplier: f64,
+    pub duration: f32,
+    pub remaining: f32,
+}
+
+impl Buff {
+    pub fn new(name: &str, effect: BuffEffect, multiplier: f64, duration: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            effect,
+            multiplier,
+            duration,
+            remaining: duration,
+        }
+    }
+}
+
+/// Shows active buffs as countdown bars in the HUD.
+pub struct BuffsPlugin;
+
+impl Plugin for BuffsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_buff_bars);
+        app.add_systems(Update, update_buff_bars.run_if(in_state(AppState::Playing)));
+    }
+}
+
+#[derive(Component)]
+struct BuffBars;
+
+#[derive(Component)]
+struct BuffBar;
+
+/// Index into `GameData::buffs` of the buff a label describes.
+#[derive(Component)]
+struct BuffBarLabel(usize);
+
+#[derive(Component)]
+struct BuffBarFill(usize);
+
+fn spawn_buff_bars(mut commands: Commands) {
+    commands.spawn((
+        BuffBars,
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(8),
+            top: px(130),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(6),
+            ..default()
+        },
+        Pickable::IGNORE,
+    ));
+}
+
+type BuffBarFillQuery<'w, 's> =
+    Query<'w, 's, (&'static BuffBarFill, &'static mut Node), Without<BuffBars>>;
+
+fn update_buff_bars(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    data: Res<GameData>,
+    containers: Query<Entity, With<BuffBars>>,
+    bars: Query<(), With<BuffBar>>,
+    mut labels: Query<(&BuffBarLabel, &mut Text)>,
+    mut fills: BuffBarFillQuery<'_, '_>,
+) {
+    let buffs = data.buffs();
+    if bars.iter().count() != buffs.len() {
+        let font_handle = asset_server.load(FONT_PATH);
+        for container in &containers {
+            commands.entity(container).despawn_children();
+            commands.entity(container).with_children(|container| {
+                for index in 0..buffs.len() {
+                    spawn_buff_bar(container, &font_handle, index);
+                }
+            });
+        }
+        return;
+    }
+
+    for (label, mut text) in &mut labels {
+        if let Some(buff) = buffs.get(label.0) {
+            text.0 = format!(
+                "{} x{} ({:.0}s)",
+                buff.name,
+                buff.multiplier,
+                buff.remaining.ceil()
+            );
+        }
+    }
+    for (fill, mut node) in &mut fills {
+        if let Some(buff) = buffs.get(fill.0) {
+            node.width = percent(buff.remaining / buff.duration * 100.0);
+        }
+    }
+}
+
+fn spawn_buff_bar(container: &mut ChildSpawnerCommands, font_handle: &Handle<Font>, index: usize) {
+    container.spawn((
+        BuffBar,
+        Node {
+            width: px(BUFF_BAR_WIDTH),
+            padding: UiRect::all(px(6)),
+            border: UiRect::all(px(1)),
+            border_radius: BorderRadius::all(px(4)),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(4),
+            ..default()
+        },
+        BackgroundColor(PANEL_BACKGROUND),
+        BorderColor::all(PANEL_BORDER),
+        children![
+            (
+                BuffBarLabel(index),
+                control_text(font_handle, "", BUFF_FONT_SIZE, CONTROL_TEXT),
+            ),
+            (
+                Node {
+                    width: percent(100),
+                    height: px(4),
+                    ..default()
+                },
+                BackgroundColor(CONTROL_TRACK),
+                children![(
+                    BuffBarFill(index),
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    BackgroundColor(CONTROL_ACCENT),
+                )],
+            ),
+        ],
+    ));
+}
@@ -0,0 +1,60 @@
+//! Minimal text clipboard access for save codes. Native builds use the system
+//! clipboard; the web build falls back to browser prompts, since the async
+//! clipboard API needs a user gesture Bevy's event loop cannot hand over.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::sync::Mutex;
+
+    /// Kept alive for the whole run: on X11 the copied text is only served
+    /// while the clipboard handle that set it exists.
+    static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+    fn with_clipboard<T>(
+        action: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, String> {
+        let mut clipboard = CLIPBOARD.lock().map_err(|err| err.to_string())?;
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new().map_err(|err| err.to_string())?);
+        }
+        action(clipboard.as_mut().unwrap()).map_err(|err| err.to_string())
+    }
+
+    pub fn copy(text: &str) -> Result<(), String> {
+        with_clipboard(|clipboard| clipboard.set_text(text))
+    }
+
+    pub fn paste() -> Result<Option<String>, String> {
+        with_clipboard(|clipboard| clipboard.get_text()).map(Some)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    fn window() -> Result<web_sys::Window, String> {
+        web_sys::window().ok_or_else(|| "no browser window".to_string())
+    }
+
+    pub fn copy(text: &str) -> Result<(), String> {
+        window()?
+            .prompt_with_message_and_default("Copy your save code:", text)
+            .map(|_| ())
+            .map_err(|err| format!("{err:?}"))
+    }
+
+    pub fn paste() -> Result<Option<String>, String> {
+        window()?
+            .prompt_with_message("Paste your save code:")
+            .map_err(|err| format!("{err:?}"))
+    }
+}
+
+/// Puts `text` on the clipboard.
+pub fn copy(text: &str) -> Result<(), String> {
+    platform::copy(text)
+}
+
+/// Reads text from the clipboard, with `Ok(None)` meaning the player cancelled.
+pub fn paste() -> Result<Option<String>, String> {
+    platform::paste()
+}
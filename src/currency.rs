@@ -0,0 +1,437 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Sub, SubAssign},
+};
+
+/// Amounts below this are stored as a plain `f64` so whole numbers stay exact;
+/// larger ones switch to scientific form.
+const EXACT_LIMIT: f64 = 1e15;
+const EXACT_EXPONENT: i64 = 15;
+/// Beyond this many orders of magnitude the smaller operand of an addition
+/// no longer affects the mantissa.
+const MAX_PRECISION_DIGITS: i64 = 17;
+/// Exponents saturate here, so adding two never overflows. Saves with larger
+/// exponents are rejected.
+const MAX_EXPONENT: i64 = i64::MAX / 4;
+/// Short-scale suffixes for each group of three digits from a million up.
+const SUFFIXES: [&str; 10] = ["M", "B", "T", "Qa", "Qi", "Sx", "Sp", "Oc", "No", "Dc"];
+
+/// A non-negative amount of currency that cannot overflow, stored as
+/// `mantissa * 10^exponent`.
+///
+/// Amounts below 10^15 are held exactly with an exponent of zero. Larger ones
+/// keep the mantissa in `1.0..10.0` and trade the low digits for range, which
+/// is plenty for costs that grow geometrically.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Currency {
+    mantissa: f64,
+    exponent: i64,
+}
+
+impl Currency {
+    pub const ZERO: Self = Self {
+        mantissa: 0.0,
+        exponent: 0,
+    };
+
+    /// Builds an amount from a whole number, usable in constants.
+    pub const fn new(value: u64) -> Self {
+        if (value as f64) < EXACT_LIMIT {
+            return Self {
+                mantissa: value as f64,
+                exponent: 0,
+            };
+        }
+        let mut mantissa = value as f64;
+        let mut exponent = 0;
+        while mantissa >= 10.0 {
+            mantissa /= 10.0;
+            exponent += 1;
+        }
+        Self { mantissa, exponent }
+    }
+
+    /// Converts an `f64`, treating negative, NaN and infinite values as zero.
+    pub fn from_f64(value: f64) -> Self {
+        Self::normalized(value, 0)
+    }
+
+    /// The amount `10^log`, for values built up in log space.
+    pub fn from_log10(log: f64) -> Self {
+        if !log.is_finite() {
+            return Self::ZERO;
+        }
+        if log < EXACT_EXPONENT as f64 {
+            return Self::from_f64(10f64.powf(log));
+        }
+        let exponent = log.floor();
+        Self::normalized(10f64.powf(log - exponent), exponent as i64)
+    }
+
+    /// `base^power`, computed without overflowing `f64`.
+    pub fn powf(base: f64, power: f64) -> Self {
+        let direct = base.powf(power);
+        if direct < EXACT_LIMIT {
+            Self::from_f64(direct)
+        } else {
+            Self::from_log10(power * base.log10())
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.mantissa == 0.0
+    }
+
+    /// The amount as an `f64`, which is infinite beyond `f64::MAX`.
+    pub fn to_f64(self) -> f64 {
+        if self.exponent > f64::MAX_10_EXP as i64 {
+            return f64::INFINITY;
+        }
+        self.mantissa * 10f64.powi(self.exponent as i32)
+    }
+
+    pub fn log10(self) -> f64 {
+        self.mantissa.log10() + self.exponent as f64
+    }
+
+    pub fn sqrt(self) -> Self {
+        if self.exponent == 0 {
+            Self::from_f64(self.mantissa.sqrt())
+        } else {
+            Self::from_log10(self.log10() / 2.0)
+        }
+    }
+
+    /// Rounds down to a whole amount. Amounts in scientific form have no
+    /// fractional digits left to drop.
+    pub fn floor(self) -> Self {
+        if self.exponent == 0 {
+            Self::from_f64(self.mantissa.floor())
+        } else {
+            self
+        }
+    }
+
+    /// Mantissa in `1.0..10.0` and exponent, whichever form the amount is in.
+    fn scientific(self) -> (f64, i64) {
+        if self.exponent != 0 || self.mantissa == 0.0 {
+            return (self.mantissa, self.exponent);
+        }
+        let shift = self.mantissa.log10().floor() as i64;
+        (self.mantissa / 10f64.powi(shift as i32), shift)
+    }
+
+    fn normalized(mantissa: f64, exponent: i64) -> Self {
+        if !mantissa.is_finite() || mantissa <= 0.0 {
+            return Self::ZERO;
+        }
+        if exponent == 0 && mantissa < EXACT_LIMIT {
+            return Self { mantissa, exponent };
+        }
+
+        let shift = mantissa.log10().floor() as i64;
+        let mut mantissa = mantissa / 10f64.powi(shift as i32);
+        let mut exponent = exponent.saturating_add(shift);
+        if mantissa >= 10.0 {
+            mantissa /= 10.0;
+            exponent = exponent.saturating_add(1);
+        } else if mantissa < 1.0 {
+            mantissa *= 10.0;
+            exponent = exponent.saturating_sub(1);
+        }
+
+        let exponent = exponent.clamp(-MAX_EXPONENT, MAX_EXPONENT);
+        if exponent < EXACT_EXPONENT {
+            Self {
+                mantissa: mantissa * 10f64.powi(exponent.max(i32::MIN as i64) as i32),
+                exponent: 0,
+            }
+        } else {
+            Self { mantissa, exponent }
+        }
+    }
+}
+
+impl From<u64> for Currency {
+    fn from(value: u64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialOrd for Currency {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        // Amounts in scientific form always have a non-zero exponent and are
+        // larger than any exact amount.
+        Some(
+            self.exponent
+                .cmp(&other.exponent)
+                .then(self.mantissa.total_cmp(&other.mantissa)),
+        )
+    }
+}
+
+impl Add for Currency {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        if self.exponent == 0 && rhs.exponent == 0 {
+            return Self::normalized(self.mantissa + rhs.mantissa, 0);
+        }
+        let (larger, smaller) = if self >= rhs {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        let (large_mantissa, large_exponent) = larger.scientific();
+        let (small_mantissa, small_exponent) = smaller.scientific();
+        let gap = large_exponent - small_exponent;
+        if smaller.is_zero() || gap > MAX_PRECISION_DIGITS {
+            return larger;
+        }
+        Self::normalized(
+            large_mantissa + small_mantissa / 10f64.powi(gap as i32),
+            large_exponent,
+        )
+    }
+}
+
+impl AddAssign for Currency {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// Saturates at zero, since an amount of currency is never negative.
+impl Sub for Currency {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        if rhs >= self {
+            return Self::ZERO;
+        }
+        if self.exponent == 0 {
+            return Self::normalized(self.mantissa - rhs.mantissa, 0);
+        }
+        let (mantissa, exponent) = self.scientific();
+        let (rhs_mantissa, rhs_exponent) = rhs.scientific();
+        let gap = exponent - rhs_exponent;
+        if rhs.is_zero() || gap > MAX_PRECISION_DIGITS {
+            return self;
+        }
+        Self::normalized(mantissa - rhs_mantissa / 10f64.powi(gap as i32), exponent)
+    }
+}
+
+impl SubAssign for Currency {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul for Currency {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let exact = self.mantissa * rhs.mantissa;
+        if self.exponent == 0 && rhs.exponent == 0 && exact < EXACT_LIMIT {
+            return Self::normalized(exact, 0);
+        }
+        let (mantissa, exponent) = self.scientific();
+        let (rhs_mantissa, rhs_exponent) = rhs.scientific();
+        Self::normalized(mantissa * rhs_mantissa, exponent + rhs_exponent)
+    }
+}
+
+impl Mul<f64> for Currency {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        self * Self::from_f64(rhs)
+    }
+}
+
+impl Div<f64> for Currency {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self {
+        if self.exponent == 0 {
+            Self::normalized(self.mantissa / rhs, 0)
+        } else {
+            self * rhs.recip()
+        }
+    }
+}
+
+impl Sum for Currency {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Currency> for Currency {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Whole amounts up to a million are written out in full; larger ones use a
+/// short-scale suffix and then scientific notation, with the formatter's
+/// precision (two by default) as the number of decimals.
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exponent == 0 && self.mantissa < 1e6 {
+            return write!(f, "{}", self.mantissa.floor());
+        }
+        let precision = f.precision().unwrap_or(2);
+        let (mantissa, exponent) = self.scientific();
+        let group = exponent / 3;
+        match SUFFIXES.get((group - 2) as usize) {
+            Some(suffix) => {
+                let scaled = mantissa * 10f64.powi((exponent - group * 3) as i32);
+                write!(f, "{scaled:.precision$} {suffix}")
+            }
+            None => write!(f, "{mantissa:.precision$}e{exponent}"),
+        }
+    }
+}
+
+/// Exact amounts are written as plain JSON numbers, as in saves from before
+/// this type existed. Larger amounts are written as strings like `"1.5e21"`.
+impl serde::Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.exponent != 0 {
+            serializer.serialize_str(&format!("{}e{}", self.mantissa, self.exponent))
+        } else if self.mantissa.fract() == 0.0 {
+            serializer.serialize_u64(self.mantissa as u64)
+        } else {
+            serializer.serialize_f64(self.mantissa)
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CurrencyVisitor)
+    }
+}
+
+struct CurrencyVisitor;
+
+impl CurrencyVisitor {
+    fn non_negative<E: serde::de::Error>(value: f64) -> Result<Currency, E> {
+        if value.is_finite() && value >= 0.0 {
+            Ok(Currency::from_f64(value))
+        } else {
+            Err(E::custom(format!("{value} is not a valid amount")))
+        }
+    }
+}
+
+impl serde::de::Visitor<'_> for CurrencyVisitor {
+    type Value = Currency;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a non-negative number or a string like \"1.5e21\"")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Currency, E> {
+        Ok(Currency::new(value))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Currency, E> {
+        Self::non_negative(value as f64)
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Currency, E> {
+        Self::non_negative(value)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Currency, E> {
+        let invalid = || E::custom(format!("{value:?} is not a valid amount"));
+        let Some((mantissa, exponent)) = value.split_once(['e', 'E']) else {
+            return Self::non_negative(value.parse().map_err(|_| invalid())?);
+        };
+        let mantissa: f64 = mantissa.parse().map_err(|_| invalid())?;
+        let exponent: i64 = exponent.parse().map_err(|_| invalid())?;
+        if !mantissa.is_finite() || mantissa < 0.0 || exponent.abs() > MAX_EXPONENT {
+            return Err(invalid());
+        }
+        Ok(Currency::normalized(mantissa, exponent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_amounts_stay_exact() {
+        let mut total = Currency::ZERO;
+        for _ in 0..10_000 {
+            total += Currency::new(1);
+        }
+
+        assert_eq!(total, Currency::new(10_000));
+        assert_eq!(total - Currency::new(9_999), Currency::new(1));
+        assert_eq!(Currency::new(3) - Currency::new(5), Currency::ZERO);
+        assert_eq!(
+            Currency::new(1_893_214) * 1.25,
+            Currency::from_f64(2_366_517.5)
+        );
+    }
+
+    #[test]
+    fn large_amounts_do_not_overflow() {
+        let cost = Currency::new(1_562_500) * Currency::powf(2.0, 500.0);
+
+        let expected = 1_562_500.0 * 2f64.powi(500);
+        assert!((cost.to_f64() / expected - 1.0).abs() < 1e-9);
+        assert!(cost > Currency::new(u64::MAX));
+        assert!(Currency::new(u64::MAX) > Currency::new(999_999_999_999_999));
+        assert_eq!(cost + Currency::new(1), cost);
+        assert!((((cost + cost) - cost).to_f64() / expected - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn amounts_are_formatted_with_suffixes() {
+        assert_eq!(Currency::new(482).to_string(), "482");
+        assert_eq!(Currency::from_f64(12.9).to_string(), "12");
+        assert_eq!(Currency::new(1_893_214).to_string(), "1.89 M");
+        assert_eq!(format!("{:.1}", Currency::new(25_000_000_000)), "25.0 B");
+        assert_eq!(Currency::from_log10(40.5).to_string(), "3.16e40");
+    }
+
+    #[test]
+    fn serde_reads_plain_numbers_and_round_trips_large_amounts() {
+        let amounts: Vec<Currency> = serde_json::from_str(r#"[584, 12.5, "2.5e30", "7"]"#).unwrap();
+
+        assert_eq!(amounts[0], Currency::new(584));
+        assert_eq!(amounts[1], Currency::from_f64(12.5));
+        assert_eq!(amounts[2], Currency::from_log10(30.0) * 2.5);
+        assert_eq!(amounts[3], Currency::new(7));
+
+        let json = serde_json::to_string(&amounts).unwrap();
+        assert_eq!(json, r#"[584,12.5,"2.5e30",7]"#);
+        assert_eq!(
+            serde_json::from_str::<Vec<Currency>>(&json).unwrap(),
+            amounts
+        );
+        assert!(serde_json::from_str::<Currency>("-4").is_err());
+        assert!(serde_json::from_str::<Currency>(r#""lots""#).is_err());
+    }
+
+    #[test]
+    fn extreme_exponents_saturate_instead_of_wrapping() {
+        let read = |json: &str| serde_json::from_str::<Currency>(json);
+
+        assert!(read(r#""10e9223372036854775807""#).is_err());
+        assert_eq!(read(r#""1e4294967301""#).unwrap().to_f64(), f64::INFINITY);
+        assert_eq!(read(r#""1e-4294967296""#).unwrap(), Currency::ZERO);
+
+        let huge = read(&format!(r#""9.9e{MAX_EXPONENT}""#)).unwrap();
+        assert_eq!((huge * huge).log10().floor(), MAX_EXPONENT as f64);
+        assert_eq!(Currency::from_log10(1e300).log10(), huge.log10());
+    }
+}
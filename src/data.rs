@@ -1,7 +1,14 @@
+use crate::{
+    buffs::{Buff, BuffEffect, BuffStacking},
+    currency::Currency,
+    definitions::Definitions,
+    synergies::active_synergies,
+    upgrades::{UpgradeDefinition, UpgradeEffect},
+};
 use bevy::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct AudioSettings {
     pub volume: f32,
     pub play_pickup: bool,
@@ -16,25 +23,133 @@ impl Default for AudioSettings {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// When an auto-buyer is allowed to spend.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AutoBuyPolicy {
+    /// Buy whenever the price is affordable.
+    Always,
+    /// Only buy if at least this much Entropy is left afterwards.
+    KeepReserve(Currency),
+    /// Only buy if the purchase earns its price back within this many seconds.
+    MaxPayback(f64),
+}
+
+/// A variant's auto-buyer settings. They are kept even while the auto-buyer
+/// is locked, so they come back after waking up.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AutoBuyer {
+    pub enabled: bool,
+    /// Seconds between purchase attempts.
+    pub interval: f32,
+    pub policy: AutoBuyPolicy,
+}
+
+impl Default for AutoBuyer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 5.0,
+            policy: AutoBuyPolicy::Always,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
 pub struct AutomatonStats {
     pub distance_from_origin: f32,
     pub cooldown: f32,
-    pub currency_per_tick: u64,
+    pub currency_per_tick: Currency,
     pub scale: f32,
-    pub base_cost: u64,
+    pub base_cost: Currency,
     pub ratio: f64,
     pub rotation: f32,
-    pub level_up_cost: u64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How many automatons each level-up consumes.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub enum LevelCostCurve {
+    Linear {
+        base: u64,
+        step: u64,
+    },
+    Geometric {
+        base: u64,
+        ratio: f64,
+    },
+    /// One cost per level; there are no levels past the end of the table.
+    Table(Vec<u64>),
+}
+
+impl LevelCostCurve {
+    /// Cost of going from `level` to `level + 1`.
+    pub fn cost(&self, level: u32) -> Option<u64> {
+        match self {
+            LevelCostCurve::Linear { base, step } => {
+                Some(base.saturating_add(step.saturating_mul(level as u64)))
+            }
+            LevelCostCurve::Geometric { base, ratio } => {
+                Some((*base as f64 * ratio.powi(level as i32)).round() as u64)
+            }
+            LevelCostCurve::Table(costs) => costs.get(level as usize).copied(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum MilestoneBonus {
+    ProductionMultiplier(f64),
+    CooldownMultiplier(f32),
+}
+
+impl MilestoneBonus {
+    pub fn describe(self) -> String {
+        match self {
+            MilestoneBonus::ProductionMultiplier(multiplier) => format!("production x{multiplier}"),
+            MilestoneBonus::CooldownMultiplier(multiplier) => format!("cooldown x{multiplier}"),
+        }
+    }
+}
+
+/// A bonus that applies from `level` onwards.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub struct LevelMilestone {
+    pub level: u32,
+    pub bonus: MilestoneBonus,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct Leveling {
+    pub cost: LevelCostCurve,
+    #[serde(default)]
+    pub max_level: Option<u32>,
+    #[serde(default)]
+    pub milestones: Vec<LevelMilestone>,
+}
+
+impl Leveling {
+    pub fn cost(&self, level: u32) -> Option<u64> {
+        if self.max_level.is_some_and(|max| level >= max) {
+            return None;
+        }
+        self.cost.cost(level)
+    }
+
+    /// Milestone bonuses reached at `level`.
+    pub fn bonuses_at(&self, level: u32) -> impl Iterator<Item = MilestoneBonus> + '_ {
+        self.milestones
+            .iter()
+            .filter(move |milestone| milestone.level <= level)
+            .map(|milestone| milestone.bonus)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
 pub enum SourceKind {
     Portal,
-    Automaton { asset_name: &'static str },
+    Automaton { asset_name: String },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
 pub enum UnlockRequirement {
     None,
     FirstPurchaseCost,
@@ -42,10 +157,19 @@ pub enum UnlockRequirement {
         variant: AutomatonVariant,
         quantity: u64,
     },
+    LifetimeEntropy(Currency),
+    VariantLevel {
+        variant: AutomatonVariant,
+        level: u32,
+    },
+    PortalClicks(u64),
+    WakeUps(u32),
+    All(Vec<UnlockRequirement>),
+    Any(Vec<UnlockRequirement>),
 }
 
 impl UnlockRequirement {
-    fn is_met(self, source: AutomatonVariant, game_data: &GameData) -> bool {
+    pub fn is_met(&self, source: AutomatonVariant, game_data: &GameData) -> bool {
         match self {
             UnlockRequirement::None => true,
             UnlockRequirement::FirstPurchaseCost => {
@@ -53,20 +177,51 @@ impl UnlockRequirement {
                     || game_data.can_afford_source(source)
             }
             UnlockRequirement::PreviousAutomaton { variant, quantity } => {
-                game_data.get_quantity_owned_by_source(variant) >= quantity
+                game_data.get_quantity_owned_by_source(*variant) >= *quantity
+            }
+            UnlockRequirement::LifetimeEntropy(amount) => game_data.total_generated() >= *amount,
+            UnlockRequirement::VariantLevel { variant, level } => {
+                game_data.get_level(*variant) >= *level
+            }
+            UnlockRequirement::PortalClicks(count) => game_data.get_portal_clicks() >= *count,
+            UnlockRequirement::WakeUps(count) => game_data.get_prestige_count() >= *count,
+            UnlockRequirement::All(requirements) => {
+                requirements.iter().all(|r| r.is_met(source, game_data))
+            }
+            UnlockRequirement::Any(requirements) => {
+                requirements.iter().any(|r| r.is_met(source, game_data))
             }
         }
     }
+
+    /// Variants whose progress this requirement depends on, at any depth.
+    pub fn required_variants(&self) -> Vec<AutomatonVariant> {
+        match self {
+            UnlockRequirement::PreviousAutomaton { variant, .. }
+            | UnlockRequirement::VariantLevel { variant, .. } => vec![*variant],
+            UnlockRequirement::All(requirements) | UnlockRequirement::Any(requirements) => {
+                requirements
+                    .iter()
+                    .flat_map(UnlockRequirement::required_variants)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// One entry of `assets/data/sources.json`.
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct SourceDefinition {
     pub variant: AutomatonVariant,
-    pub display_name: &'static str,
-    pub plural_display_name: &'static str,
+    pub display_name: String,
+    pub plural_display_name: String,
     pub kind: SourceKind,
     pub stats: AutomatonStats,
     pub unlock_requirement: UnlockRequirement,
+    /// Level-up costs and bonuses. Only automatons level up.
+    #[serde(default)]
+    pub leveling: Option<Leveling>,
 }
 
 impl SourceDefinition {
@@ -74,8 +229,8 @@ impl SourceDefinition {
         matches!(self.kind, SourceKind::Automaton { .. })
     }
 
-    pub fn asset_name(&self) -> Option<&'static str> {
-        match self.kind {
+    pub fn asset_name(&self) -> Option<&str> {
+        match &self.kind {
             SourceKind::Automaton { asset_name } => Some(asset_name),
             SourceKind::Portal => None,
         }
@@ -90,6 +245,14 @@ impl SourceDefinition {
         self.asset_name()
             .map(|asset_name| format!("{asset_name}_ring"))
     }
+
+    pub fn label_for_quantity(&self, quantity: u64) -> &str {
+        if quantity == 1 {
+            &self.display_name
+        } else {
+            &self.plural_display_name
+        }
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -106,296 +269,530 @@ pub enum AutomatonVariant {
 }
 
 impl AutomatonVariant {
-    pub fn definition(self) -> &'static SourceDefinition {
-        source_definition(self)
-    }
-
-    pub fn stats(self) -> &'static AutomatonStats {
-        &self.definition().stats
-    }
+    pub const ALL: [AutomatonVariant; 9] = [
+        AutomatonVariant::Portal,
+        AutomatonVariant::Hellmite,
+        AutomatonVariant::Abyssopod,
+        AutomatonVariant::GapingDubine,
+        AutomatonVariant::GazingHoku,
+        AutomatonVariant::Lorgner,
+        AutomatonVariant::PelteLacerte,
+        AutomatonVariant::Struthios,
+        AutomatonVariant::WoolyChionoescent,
+    ];
 
+    /// Every variant but the portal is an automaton; source validation
+    /// holds the definitions to that.
     pub fn is_automaton(self) -> bool {
-        self.definition().is_automaton()
+        self != AutomatonVariant::Portal
     }
+}
 
-    pub fn display_name(self) -> &'static str {
-        self.definition().display_name
-    }
+const LEVEL_MULTIPLIER_BASE: f64 = 1.25;
+/// Lifetime Entropy worth one Lucidity; rewards grow with the square root of
+/// lifetime Entropy over this.
+const LUCIDITY_BASE: f64 = 1e6;
+/// Production bonus per Lucidity held.
+const LUCIDITY_BONUS: f64 = 0.02;
+/// Share of the current price of the banished automatons paid back.
+const BANISH_REFUND_FRACTION: f64 = 0.5;
+/// Chance of a critical portal click before upgrades.
+const BASE_CRIT_CHANCE: f64 = 0.02;
+/// How much a critical portal click multiplies its Entropy.
+pub const CRIT_MULTIPLIER: f64 = 10.0;
+/// Shortest cooldown reductions can bring an automaton to, so rates stay finite.
+pub const MIN_COOLDOWN: f32 = 0.05;
+/// Most automatons of one variant that can be owned, so counts stay exact in
+/// floating point and a flat price curve can't run buy-max past `u64`.
+pub const MAX_OWNED_PER_SOURCE: u64 = 1_000_000_000_000;
 
-    pub fn label_for_quantity(self, quantity: u64) -> &'static str {
-        if quantity == 1 {
-            self.definition().display_name
-        } else {
-            self.definition().plural_display_name
+/// Controls how much a player earns for time spent with the game closed.
+#[derive(Resource, Debug, Clone)]
+pub struct OfflineProgressSettings {
+    /// Absences shorter than this many seconds earn nothing.
+    pub min_seconds: u64,
+    /// Absences longer than this many seconds are credited as this long.
+    pub cap_seconds: u64,
+    /// Fraction of the normal production rate earned while away.
+    pub efficiency: f64,
+}
+
+impl Default for OfflineProgressSettings {
+    fn default() -> Self {
+        Self {
+            min_seconds: 60,
+            cap_seconds: 8 * 60 * 60,
+            efficiency: 0.5,
         }
     }
 }
 
-impl std::fmt::Display for AutomatonVariant {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.display_name())
+/// What was credited for an absence, kept around until the player dismisses
+/// the summary.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct OfflineReport {
+    pub away_seconds: u64,
+    pub credited_seconds: u64,
+    pub earnings: Vec<(AutomatonVariant, Currency)>,
+}
+
+impl OfflineReport {
+    pub fn total(&self) -> Currency {
+        self.earnings.iter().map(|(_, amount)| amount).sum()
     }
 }
 
-pub const SOURCE_DEFINITIONS: [SourceDefinition; 9] = [
-    SourceDefinition {
-        variant: AutomatonVariant::Hellmite,
-        display_name: "Hellmite",
-        plural_display_name: "Hellmites",
-        kind: SourceKind::Automaton {
-            asset_name: "hellmite",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 2.5,
-            cooldown: 2.5,
-            currency_per_tick: 1,
-            scale: 0.25,
-            base_cost: 25,
-            ratio: 1.05,
-            rotation: 0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::FirstPurchaseCost,
-    },
-    SourceDefinition {
-        variant: AutomatonVariant::Abyssopod,
-        display_name: "Abyssopod",
-        plural_display_name: "Abyssopods",
-        kind: SourceKind::Automaton {
-            asset_name: "abyssopod",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 3.5,
-            cooldown: 7.5,
-            currency_per_tick: 20,
-            scale: 0.35,
-            base_cost: 100,
-            ratio: 1.1,
-            rotation: -0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::Hellmite,
-            quantity: 20,
-        },
+/// Something that happened to the economy. `GameData` queues these as it
+/// changes, and the economy plugin sends each one on as a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    SourcePurchased {
+        source: AutomatonVariant,
+        count: u64,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::GapingDubine,
-        display_name: "Gaping Dubine",
-        plural_display_name: "Gaping Dubines",
-        kind: SourceKind::Automaton {
-            asset_name: "gaping_dubine",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 5.0,
-            cooldown: 15.0,
-            currency_per_tick: 45,
-            scale: 0.5,
-            base_cost: 500,
-            ratio: 1.25,
-            rotation: 0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::Abyssopod,
-            quantity: 15,
-        },
+    SourceLeveledUp {
+        source: AutomatonVariant,
+        level: u32,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::GazingHoku,
-        display_name: "Gazing Hoku",
-        plural_display_name: "Gazing Hokus",
-        kind: SourceKind::Automaton {
-            asset_name: "gazing_hoku",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 7.0,
-            cooldown: 30.0,
-            currency_per_tick: 120,
-            scale: 0.6,
-            base_cost: 2500,
-            ratio: 1.45,
-            rotation: -0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::GapingDubine,
-            quantity: 10,
-        },
+    SourceBanished {
+        source: AutomatonVariant,
+        count: u64,
+        refund: Currency,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::Lorgner,
-        display_name: "Lorgner",
-        plural_display_name: "Lorgners",
-        kind: SourceKind::Automaton {
-            asset_name: "lorgner",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 10.0,
-            cooldown: 50.0,
-            currency_per_tick: 625,
-            scale: 0.75,
-            base_cost: 12500,
-            ratio: 1.6,
-            rotation: 0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::GazingHoku,
-            quantity: 8,
-        },
+    IncomeEarned {
+        source: AutomatonVariant,
+        amount: Currency,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::PelteLacerte,
-        display_name: "Pelte Lacerte",
-        plural_display_name: "Pelte Lacertes",
-        kind: SourceKind::Automaton {
-            asset_name: "pelte_lacerte",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 13.0,
-            cooldown: 60.0,
-            currency_per_tick: 1500,
-            scale: 0.8,
-            base_cost: 62500,
-            ratio: 1.75,
-            rotation: -0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::Lorgner,
-            quantity: 6,
-        },
+    UpgradePurchased {
+        id: String,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::Struthios,
-        display_name: "Struthios",
-        plural_display_name: "Struthios",
-        kind: SourceKind::Automaton {
-            asset_name: "struthios",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 16.0,
-            cooldown: 90.0,
-            currency_per_tick: 5000,
-            scale: 0.9,
-            base_cost: 62500,
-            ratio: 1.8,
-            rotation: 0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::PelteLacerte,
-            quantity: 5,
-        },
+    BuffAdded {
+        buff: Buff,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::WoolyChionoescent,
-        display_name: "Wooly Chionoescent",
-        plural_display_name: "Wooly Chionoescents",
-        kind: SourceKind::Automaton {
-            asset_name: "wooly_chionoescent",
-        },
-        stats: AutomatonStats {
-            distance_from_origin: 20.0,
-            cooldown: 120.0,
-            currency_per_tick: 150000,
-            scale: 1.0,
-            base_cost: 1562500,
-            ratio: 2.0,
-            rotation: -0.05,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::PreviousAutomaton {
-            variant: AutomatonVariant::Struthios,
-            quantity: 2,
-        },
+    PortalClicked {
+        amount: Currency,
+        crit: bool,
     },
-    SourceDefinition {
-        variant: AutomatonVariant::Portal,
-        display_name: "Portal",
-        plural_display_name: "Portals",
-        kind: SourceKind::Portal,
-        stats: AutomatonStats {
-            distance_from_origin: 0.0,
-            cooldown: 1.0,
-            currency_per_tick: 0,
-            scale: 0.0,
-            base_cost: 0,
-            ratio: 1.0,
-            rotation: 0.0,
-            level_up_cost: 50,
-        },
-        unlock_requirement: UnlockRequirement::None,
+    WokeUp {
+        count: u32,
     },
-];
-
-pub fn source_definition(variant: AutomatonVariant) -> &'static SourceDefinition {
-    SOURCE_DEFINITIONS
-        .iter()
-        .find(|definition| definition.variant == variant)
-        .expect("missing source definition")
 }
 
-pub fn automaton_definitions() -> impl Iterator<Item = &'static SourceDefinition> {
-    SOURCE_DEFINITIONS
-        .iter()
-        .filter(|definition| definition.is_automaton())
-}
-
-const LEVEL_MULTIPLIER_BASE: f64 = 1.25;
-
-#[derive(Resource, Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Resource, Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GameData {
-    currency: u64,
+    currency: Currency,
     owned_by_type: HashMap<AutomatonVariant, u64>,
-    income_by_type: HashMap<AutomatonVariant, u64>,
+    income_by_type: HashMap<AutomatonVariant, Currency>,
     #[serde(default)]
     levels_by_type: HashMap<AutomatonVariant, u32>,
+    /// Times the player has woken up (prestiged).
+    #[serde(default)]
+    prestige_count: u32,
+    /// Prestige currency, kept across wake-ups.
+    #[serde(default)]
+    lucidity: Currency,
+    /// Ids of purchased upgrades. Ids no longer defined are kept but ignored.
+    #[serde(default)]
+    upgrades: BTreeSet<String>,
+    /// Portal clicks over the life of this save.
+    #[serde(default)]
+    portal_clicks: u64,
+    #[serde(default)]
+    portal_crits: u64,
+    /// Most Entropy a single portal click has earned.
+    #[serde(default)]
+    best_click: Currency,
+    /// Ids of unlocked achievements, kept across wake-ups.
+    #[serde(default)]
+    achievements: BTreeSet<String>,
+    /// Temporary multipliers, saved with the time they have left.
+    #[serde(default)]
+    buffs: Vec<Buff>,
+    #[serde(default)]
+    auto_buyers: HashMap<AutomatonVariant, AutoBuyer>,
     #[serde(default)]
     pub audio_settings: AudioSettings,
+    /// Wall-clock Unix time of the last successful save.
+    #[serde(default)]
+    last_saved: Option<u64>,
+    /// Set by every mutation; cleared when the save scheduler flushes to storage.
+    #[serde(skip)]
+    dirty: bool,
+    /// Set by mutations that should reach storage without waiting for the
+    /// next scheduled flush, such as purchases.
+    #[serde(skip)]
+    save_requested: bool,
+    /// Events not yet taken by `take_events`.
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+    /// The definitions this data is played against, kept in step with the
+    /// `Definitions` resource.
+    #[serde(skip)]
+    definitions: Definitions,
 }
 
 impl GameData {
-    pub fn restore() -> Self {
-        #[cfg(target_arch = "wasm32")]
-        {
-            if let Some(saved) = web_sys::window()
-                .and_then(|w| w.local_storage().ok().flatten())
-                .and_then(|storage| storage.get_item("game_data").ok().flatten())
-            {
-                serde_json::from_str(&saved).unwrap_or_default()
-            } else {
-                Self::default()
-            }
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    pub fn set_definitions(&mut self, definitions: Definitions) {
+        self.definitions = definitions;
+    }
+
+    pub fn get_currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Credits `amount` from `source` after multipliers, returning what was credited.
+    pub fn add_income(&mut self, source: AutomatonVariant, amount: Currency) -> Currency {
+        let scaled = self.scaled_amount(source, amount);
+        self.credit(source, scaled);
+        self.mark_dirty();
+        scaled
+    }
+
+    /// Adds `amount` to the balance and to what `source` has generated.
+    fn credit(&mut self, source: AutomatonVariant, amount: Currency) {
+        self.currency += amount;
+        *self.income_by_type.entry(source).or_default() += amount;
+        if !amount.is_zero() {
+            self.events.push(GameEvent::IncomeEarned { source, amount });
         }
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            if let Ok(saved) = std::fs::read_to_string("save_data.json") {
-                serde_json::from_str(&saved).unwrap_or_default()
-            } else {
-                Self::default()
+    /// Takes the events queued since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn has_events(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    fn scaled_amount(&self, source: AutomatonVariant, amount: Currency) -> Currency {
+        (amount * self.production_multiplier(source) * self.buff_multiplier(BuffEffect::Production))
+            .floor()
+    }
+
+    /// Everything that scales what `source` produces: its level, its upgrades,
+    /// synergies from other variants and the global multiplier.
+    pub fn production_multiplier(&self, source: AutomatonVariant) -> f64 {
+        let upgrades: f64 = self
+            .owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::VariantMultiplier {
+                    variant,
+                    multiplier,
+                } if variant == source => Some(multiplier),
+                _ => None,
+            })
+            .product();
+        let synergies: f64 = active_synergies(self, source)
+            .map(|(synergy, steps)| synergy.production_multiplier(steps))
+            .product();
+        self.level_multiplier(source) * upgrades * synergies * self.global_multiplier()
+    }
+
+    /// Production multiplier applied to every source, from Lucidity and upgrades.
+    pub fn global_multiplier(&self) -> f64 {
+        let upgrades: f64 = self
+            .owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::GlobalMultiplier { multiplier } => Some(multiplier),
+                _ => None,
+            })
+            .product();
+        Self::lucidity_multiplier(self.lucidity) * upgrades
+    }
+
+    fn lucidity_multiplier(lucidity: Currency) -> f64 {
+        1.0 + lucidity.to_f64() * LUCIDITY_BONUS
+    }
+
+    pub fn get_lucidity(&self) -> Currency {
+        self.lucidity
+    }
+
+    pub fn get_prestige_count(&self) -> u32 {
+        self.prestige_count
+    }
+
+    /// Lucidity that waking up now would add. The total ever earned depends
+    /// only on lifetime Entropy, so waking up twice in a row earns nothing
+    /// the second time.
+    pub fn pending_lucidity(&self) -> Currency {
+        let earned = (self.total_generated() / LUCIDITY_BASE).sqrt().floor();
+        earned - self.lucidity
+    }
+
+    /// Production multiplier after waking up now, which also resets upgrades.
+    pub fn global_multiplier_after_prestige(&self) -> f64 {
+        Self::lucidity_multiplier(self.lucidity + self.pending_lucidity())
+    }
+
+    pub fn can_prestige(&self) -> bool {
+        !self.pending_lucidity().is_zero()
+    }
+
+    /// Trades current Entropy, automatons, levels and upgrades for Lucidity.
+    /// Lifetime Entropy per source is kept, as it is what Lucidity is earned from.
+    pub fn prestige(&mut self) -> bool {
+        let reward = self.pending_lucidity();
+        if reward.is_zero() {
+            return false;
+        }
+        self.lucidity += reward;
+        self.prestige_count += 1;
+        self.currency = Currency::ZERO;
+        self.owned_by_type.clear();
+        self.levels_by_type.clear();
+        self.upgrades.clear();
+        self.events.push(GameEvent::WokeUp {
+            count: self.prestige_count,
+        });
+        self.request_save();
+        true
+    }
+
+    pub fn has_upgrade(&self, id: &str) -> bool {
+        self.upgrades.contains(id)
+    }
+
+    /// Whether every prerequisite of `upgrade` is owned.
+    pub fn upgrade_unlocked(&self, upgrade: &UpgradeDefinition) -> bool {
+        upgrade.prerequisites.iter().all(|id| self.has_upgrade(id))
+    }
+
+    pub fn can_purchase_upgrade(&self, upgrade: &UpgradeDefinition) -> bool {
+        !self.has_upgrade(&upgrade.id)
+            && self.upgrade_unlocked(upgrade)
+            && self.currency >= upgrade.cost
+    }
+
+    pub fn purchase_upgrade(&mut self, id: &str) -> bool {
+        let definitions = self.definitions.clone();
+        let Some(upgrade) = definitions.upgrade(id) else {
+            return false;
+        };
+        if !self.can_purchase_upgrade(upgrade) {
+            return false;
+        }
+        self.currency -= upgrade.cost;
+        self.upgrades.insert(upgrade.id.clone());
+        self.events.push(GameEvent::UpgradePurchased {
+            id: upgrade.id.clone(),
+        });
+        self.request_save();
+        true
+    }
+
+    fn owned_upgrade_effects(&self) -> impl Iterator<Item = UpgradeEffect> + '_ {
+        self.definitions
+            .upgrades
+            .iter()
+            .filter(|upgrade| self.has_upgrade(&upgrade.id))
+            .map(|upgrade| upgrade.effect)
+    }
+
+    /// Seconds between payouts of `source`, after level milestones,
+    /// synergies and cooldown upgrades.
+    pub fn cooldown(&self, source: AutomatonVariant) -> f32 {
+        let milestones: f32 = self
+            .milestone_bonuses(source, self.get_level(source))
+            .filter_map(|bonus| match bonus {
+                MilestoneBonus::CooldownMultiplier(multiplier) => Some(multiplier),
+                _ => None,
+            })
+            .product();
+        let synergies: f32 = active_synergies(self, source)
+            .map(|(synergy, steps)| synergy.cooldown_multiplier(steps))
+            .product();
+        self.owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::CooldownReduction { variant, fraction }
+                    if variant.is_none_or(|v| v == source) =>
+                {
+                    Some(1.0 - fraction)
+                }
+                _ => None,
+            })
+            .fold(
+                self.definitions.source(source).stats.cooldown * milestones * synergies,
+                |cooldown, factor| cooldown * factor,
+            )
+            .max(MIN_COOLDOWN)
+    }
+
+    /// Price growth per purchase of `source`. Discounts shrink the growth
+    /// above 1, so prices can never start falling.
+    pub fn cost_ratio(&self, source: AutomatonVariant) -> f64 {
+        let discount: f64 = self
+            .owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::CostRatioDiscount { variant, fraction }
+                    if variant.is_none_or(|v| v == source) =>
+                {
+                    Some(1.0 - fraction)
+                }
+                _ => None,
+            })
+            .product();
+        1.0 + (self.definitions.source(source).stats.ratio - 1.0) * discount
+    }
+
+    /// Credits one portal click, critical when `roll` (uniform in `0..1`)
+    /// falls under the crit chance. Returns whether it was critical.
+    pub fn click_portal(&mut self, roll: f64) -> bool {
+        let crit = roll < self.portal_crit_chance();
+        let mut earned = self.portal_click_value() * self.buff_multiplier(BuffEffect::Clicks);
+        if crit {
+            earned = earned * CRIT_MULTIPLIER;
+            self.portal_crits += 1;
+        }
+        self.portal_clicks += 1;
+        let earned = earned.floor();
+        self.credit(AutomatonVariant::Portal, earned);
+        self.mark_dirty();
+        if earned > self.best_click {
+            self.best_click = earned;
+        }
+        self.events.push(GameEvent::PortalClicked {
+            amount: earned,
+            crit,
+        });
+        crit
+    }
+
+    pub fn buffs(&self) -> &[Buff] {
+        &self.buffs
+    }
+
+    /// Grants `buff`, combining it with an active buff of the same name
+    /// according to `stacking`.
+    pub fn add_buff(&mut self, buff: Buff, stacking: BuffStacking) {
+        self.events
+            .push(GameEvent::BuffAdded { buff: buff.clone() });
+        let existing = self
+            .buffs
+            .iter_mut()
+            .find(|active| active.name == buff.name);
+        match (existing, stacking) {
+            (Some(active), BuffStacking::Refresh) => {
+                active.remaining = active.remaining.max(buff.duration);
+                active.duration = active.duration.max(buff.duration);
+            }
+            (Some(active), BuffStacking::Extend) => {
+                active.remaining += buff.duration;
+                active.duration = active.duration.max(active.remaining);
             }
+            _ => self.buffs.push(buff),
         }
+        self.request_save();
     }
-}
 
-impl GameData {
-    pub fn get_currency(&self) -> u64 {
-        self.currency
+    /// Counts every buff down by `delta` seconds and drops the expired ones.
+    pub fn tick_buffs(&mut self, delta: f32) {
+        for buff in &mut self.buffs {
+            buff.remaining -= delta;
+        }
+        let before = self.buffs.len();
+        self.buffs.retain(|buff| buff.remaining > 0.0);
+        if self.buffs.len() != before {
+            self.mark_dirty();
+        }
     }
 
-    pub fn add_income(&mut self, source: AutomatonVariant, amount: u64) {
-        let scaled = self.scaled_amount(source, amount);
-        self.currency += scaled;
-        *self.income_by_type.entry(source).or_insert(0) += scaled;
-        self.save();
+    /// Product of every active buff with `effect`.
+    pub fn buff_multiplier(&self, effect: BuffEffect) -> f64 {
+        self.buffs
+            .iter()
+            .filter(|buff| buff.effect == effect)
+            .map(|buff| buff.multiplier)
+            .product()
+    }
+
+    /// Credits `seconds` of current production at once, through the portal.
+    pub fn grant_lump_sum(&mut self, seconds: f64) -> Currency {
+        let production = Currency::from_f64(self.total_rate_per_second() * seconds).floor();
+        // Early on production is tiny, so never pay less than a click.
+        let amount = if production > self.portal_click_value() {
+            production
+        } else {
+            self.portal_click_value()
+        };
+        self.credit(AutomatonVariant::Portal, amount);
+        self.request_save();
+        amount
+    }
+
+    pub fn get_portal_clicks(&self) -> u64 {
+        self.portal_clicks
+    }
+
+    pub fn get_portal_crits(&self) -> u64 {
+        self.portal_crits
+    }
+
+    pub fn get_best_click(&self) -> Currency {
+        self.best_click
+    }
+
+    /// Entropy a normal portal click earns right now.
+    pub fn portal_click_value(&self) -> Currency {
+        self.scaled_amount(AutomatonVariant::Portal, self.portal_click_amount())
+            + self.portal_rate_share()
+    }
+
+    pub fn portal_crit_chance(&self) -> f64 {
+        let bonus: f64 = self
+            .owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::PortalCritChance { chance } => Some(chance),
+                _ => None,
+            })
+            .sum();
+        (BASE_CRIT_CHANCE + bonus).min(1.0)
+    }
+
+    pub fn has_achievement(&self, id: &str) -> bool {
+        self.achievements.contains(id)
+    }
+
+    /// Records an achievement, returning whether it was newly unlocked.
+    pub fn unlock_achievement(&mut self, id: &str) -> bool {
+        if !self.achievements.insert(id.to_string()) {
+            return false;
+        }
+        self.request_save();
+        true
     }
 
-    fn scaled_amount(&self, source: AutomatonVariant, amount: u64) -> u64 {
-        let scaled = amount as f64 * self.level_multiplier(source);
-        scaled.floor().max(0.0) as u64
+    /// Base Entropy for one portal click, before crits and multipliers.
+    pub fn portal_click_amount(&self) -> Currency {
+        self.owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::PortalClickPower { amount } => Some(amount),
+                _ => None,
+            })
+            .fold(Currency::new(1), |total, amount| total + amount)
+    }
+
+    /// Entropy a portal click adds from the automatons' rate. The rate is
+    /// already multiplied, so this is not scaled again.
+    pub fn portal_rate_share(&self) -> Currency {
+        let rate_share: f64 = self
+            .owned_upgrade_effects()
+            .filter_map(|effect| match effect {
+                UpgradeEffect::PortalRateShare { fraction } => Some(fraction),
+                _ => None,
+            })
+            .sum();
+        Currency::from_f64(
+            self.total_rate_per_second()
+                * rate_share
+                * self.buff_multiplier(BuffEffect::Production),
+        )
     }
 
     pub fn get_level(&self, source: AutomatonVariant) -> u32 {
@@ -403,61 +800,188 @@ impl GameData {
     }
 
     pub fn level_multiplier(&self, source: AutomatonVariant) -> f64 {
-        LEVEL_MULTIPLIER_BASE.powi(self.get_level(source) as i32)
+        self.level_multiplier_at(source, self.get_level(source))
+    }
+
+    /// Production multiplier of `source` at `level`, including milestone bonuses.
+    pub fn level_multiplier_at(&self, source: AutomatonVariant, level: u32) -> f64 {
+        let milestones: f64 = self
+            .milestone_bonuses(source, level)
+            .filter_map(|bonus| match bonus {
+                MilestoneBonus::ProductionMultiplier(multiplier) => Some(multiplier),
+                _ => None,
+            })
+            .product();
+        LEVEL_MULTIPLIER_BASE.powi(level as i32) * milestones
     }
 
-    pub fn cost_to_level_up(&self, source: AutomatonVariant) -> u64 {
-        source.stats().level_up_cost
+    fn milestone_bonuses(
+        &self,
+        source: AutomatonVariant,
+        level: u32,
+    ) -> impl Iterator<Item = MilestoneBonus> + '_ {
+        self.definitions
+            .source(source)
+            .leveling
+            .iter()
+            .flat_map(move |leveling| leveling.bonuses_at(level))
+    }
+
+    /// Automatons consumed by the next level-up, or `None` at the level cap.
+    pub fn cost_to_level_up(&self, source: AutomatonVariant) -> Option<u64> {
+        self.definitions
+            .source(source)
+            .leveling
+            .as_ref()
+            .and_then(|leveling| leveling.cost(self.get_level(source)))
+    }
+
+    pub fn max_level(&self, source: AutomatonVariant) -> Option<u32> {
+        self.definitions
+            .source(source)
+            .leveling
+            .as_ref()
+            .and_then(|leveling| leveling.max_level)
     }
 
     pub fn can_level_up(&self, source: AutomatonVariant) -> bool {
         source.is_automaton()
-            && self.get_quantity_owned_by_source(source) >= self.cost_to_level_up(source)
+            && self
+                .cost_to_level_up(source)
+                .is_some_and(|cost| self.get_quantity_owned_by_source(source) >= cost)
     }
 
     pub fn level_up(&mut self, source: AutomatonVariant) -> bool {
+        let Some(cost) = self.cost_to_level_up(source) else {
+            return false;
+        };
         if !self.can_level_up(source) {
             return false;
         }
-        let cost = self.cost_to_level_up(source);
         let owned = self.owned_by_type.entry(source).or_insert(0);
         *owned -= cost;
-        *self.levels_by_type.entry(source).or_insert(0) += 1;
-        self.save();
+        let level = self.levels_by_type.entry(source).or_insert(0);
+        *level += 1;
+        self.events.push(GameEvent::SourceLeveledUp {
+            source,
+            level: *level,
+        });
+        self.request_save();
         true
     }
 
-    pub fn get_currency_by_source(&self, source: AutomatonVariant) -> u64 {
-        self.income_by_type.get(&source).cloned().unwrap_or(0)
+    pub fn get_currency_by_source(&self, source: AutomatonVariant) -> Currency {
+        self.income_by_type
+            .get(&source)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn get_cost_to_add_source(&self, source: AutomatonVariant) -> Currency {
+        self.get_cost_to_add_sources(source, 1)
     }
 
-    pub fn get_cost_to_add_source(&self, source: AutomatonVariant) -> u64 {
-        let stats = source.stats();
-        let quantity_owned = self.get_quantity_owned_by_source(source);
-        f64::floor(stats.base_cost as f64 * stats.ratio.powf(quantity_owned as f64)) as u64
+    /// Total cost of the next `count` purchases of `source`, as the closed
+    /// form of the geometric series `base * ratio^owned * (ratio^count - 1) / (ratio - 1)`.
+    pub fn get_cost_to_add_sources(&self, source: AutomatonVariant, count: u64) -> Currency {
+        self.cost_of_purchases(source, self.get_quantity_owned_by_source(source), count)
+    }
+
+    /// Cost of `count` purchases of `source` made while already owning `owned`.
+    fn cost_of_purchases(&self, source: AutomatonVariant, owned: u64, count: u64) -> Currency {
+        let ratio = self.cost_ratio(source);
+        let next =
+            self.definitions.source(source).stats.base_cost * Currency::powf(ratio, owned as f64);
+        let total = if ratio == 1.0 {
+            next * count as f64
+        } else {
+            (Currency::powf(ratio, count as f64) - Currency::new(1)) * next * (ratio - 1.0).recip()
+        };
+        total.floor()
+    }
+
+    /// The largest number of `source` the current Entropy pays for at once.
+    pub fn max_affordable_sources(&self, source: AutomatonVariant) -> u64 {
+        let ratio = self.cost_ratio(source);
+        let next = self.get_cost_to_add_source(source);
+        let room = MAX_OWNED_PER_SOURCE.saturating_sub(self.get_quantity_owned_by_source(source));
+        if room == 0 || next.is_zero() || self.currency < next {
+            return 0;
+        }
+        // Invert the series: count = log_ratio(currency * (ratio - 1) / next + 1).
+        let estimate = if ratio == 1.0 {
+            10f64.powf(self.currency.log10() - next.log10())
+        } else {
+            let scaled = (self.currency * (ratio - 1.0)).log10() - next.log10();
+            let log_total = if scaled > 15.0 {
+                scaled
+            } else {
+                (10f64.powf(scaled) + 1.0).log10()
+            };
+            log_total / ratio.log10()
+        };
+
+        // The estimate comes from floating point, so settle the last step exactly.
+        let mut count = (estimate.floor() as u64).clamp(1, room);
+        while count > 1 && self.get_cost_to_add_sources(source, count) > self.currency {
+            count -= 1;
+        }
+        while count < room && self.get_cost_to_add_sources(source, count + 1) <= self.currency {
+            count += 1;
+        }
+        count
     }
 
     pub fn can_afford_source(&self, source: AutomatonVariant) -> bool {
         self.get_currency() >= self.get_cost_to_add_source(source)
     }
 
-    pub fn purchase_source(&mut self, source: AutomatonVariant) -> bool {
-        if !source.is_automaton() || !self.prerequisites_met(source) {
+    /// Buys exactly `count` of `source`, or nothing if they are not all affordable.
+    pub fn purchase_sources(&mut self, source: AutomatonVariant, count: u64) -> bool {
+        if count == 0 || !source.is_automaton() || !self.prerequisites_met(source) {
             return false;
         }
-        let cost = self.get_cost_to_add_source(source);
-        if self.can_afford_source(source) {
-            self.currency -= cost;
-            self.increase_quantity_owned_by_source(source);
-            true
-        } else {
-            false
+        let owned = self.get_quantity_owned_by_source(source);
+        if owned.saturating_add(count) > MAX_OWNED_PER_SOURCE {
+            return false;
+        }
+        let cost = self.get_cost_to_add_sources(source, count);
+        if self.currency < cost {
+            return false;
         }
+        self.currency -= cost;
+        *self.owned_by_type.entry(source).or_insert(0) += count;
+        self.events
+            .push(GameEvent::SourcePurchased { source, count });
+        self.request_save();
+        true
     }
 
-    pub fn increase_quantity_owned_by_source(&mut self, source: AutomatonVariant) {
-        *self.owned_by_type.entry(source).or_insert(0) += 1;
-        self.save();
+    /// Entropy paid back for banishing the last `count` of `source`: a share
+    /// of what buying them back would cost, so the curve is walked backwards.
+    pub fn refund_for_sources(&self, source: AutomatonVariant, count: u64) -> Currency {
+        let owned = self.get_quantity_owned_by_source(source);
+        let count = count.min(owned);
+        (self.cost_of_purchases(source, owned - count, count) * BANISH_REFUND_FRACTION).floor()
+    }
+
+    /// Banishes exactly `count` of `source` for a refund, or nothing if fewer
+    /// are owned. The refund is not counted as generated Entropy.
+    pub fn sell_source(&mut self, source: AutomatonVariant, count: u64) -> bool {
+        if count == 0 || !source.is_automaton() || self.get_quantity_owned_by_source(source) < count
+        {
+            return false;
+        }
+        let refund = self.refund_for_sources(source, count);
+        *self.owned_by_type.entry(source).or_insert(0) -= count;
+        self.currency += refund;
+        self.events.push(GameEvent::SourceBanished {
+            source,
+            count,
+            refund,
+        });
+        self.request_save();
+        true
     }
 
     pub fn get_quantity_owned_by_source(&self, source: AutomatonVariant) -> u64 {
@@ -465,18 +989,137 @@ impl GameData {
     }
 
     pub fn rate_per_second_by_source(&self, source: AutomatonVariant) -> f64 {
-        let stats = source.stats();
-        let raw = stats.currency_per_tick as f64 / stats.cooldown as f64
-            * self.get_quantity_owned_by_source(source) as f64;
-        raw * self.level_multiplier(source)
+        self.rate_per_automaton(source) * self.get_quantity_owned_by_source(source) as f64
+    }
+
+    /// Entropy per second a single `source` adds at current multipliers.
+    fn rate_per_automaton(&self, source: AutomatonVariant) -> f64 {
+        let stats = &self.definitions.source(source).stats;
+        stats.currency_per_tick.to_f64() / self.cooldown(source) as f64
+            * self.production_multiplier(source)
+    }
+
+    /// Seconds the next `source` takes to earn back its price, or `None` if it
+    /// produces nothing.
+    pub fn payback_seconds(&self, source: AutomatonVariant) -> Option<f64> {
+        let rate = self.rate_per_automaton(source);
+        (rate > 0.0).then(|| self.get_cost_to_add_source(source).to_f64() / rate)
+    }
+
+    /// Whether an owned upgrade unlocks the auto-buyer for `source`.
+    pub fn auto_buyer_unlocked(&self, source: AutomatonVariant) -> bool {
+        source.is_automaton()
+            && self.owned_upgrade_effects().any(|effect| {
+                matches!(effect, UpgradeEffect::AutoBuyer { variant } if variant.is_none_or(|v| v == source))
+            })
+    }
+
+    pub fn auto_buyer(&self, source: AutomatonVariant) -> AutoBuyer {
+        self.auto_buyers.get(&source).copied().unwrap_or_default()
+    }
+
+    pub fn set_auto_buyer(&mut self, source: AutomatonVariant, auto_buyer: AutoBuyer) {
+        self.auto_buyers.insert(source, auto_buyer);
+        self.mark_dirty();
+    }
+
+    /// Buys one `source` through `purchase_sources` if its auto-buyer is
+    /// unlocked, enabled and its policy allows the price.
+    pub fn auto_buy(&mut self, source: AutomatonVariant) -> bool {
+        let auto_buyer = self.auto_buyer(source);
+        if !auto_buyer.enabled || !self.auto_buyer_unlocked(source) {
+            return false;
+        }
+        let allowed = match auto_buyer.policy {
+            AutoBuyPolicy::Always => true,
+            AutoBuyPolicy::KeepReserve(reserve) => {
+                self.currency >= self.get_cost_to_add_source(source) + reserve
+            }
+            AutoBuyPolicy::MaxPayback(seconds) => self
+                .payback_seconds(source)
+                .is_some_and(|payback| payback <= seconds),
+        };
+        allowed && self.purchase_sources(source, 1)
+    }
+
+    /// Entropy per second from every automaton together.
+    pub fn total_rate_per_second(&self) -> f64 {
+        self.definitions
+            .automatons()
+            .map(|definition| self.rate_per_second_by_source(definition.variant))
+            .sum()
+    }
+
+    /// Credits production for the time between the last save and `now`,
+    /// using each variant's current rate.
+    pub fn apply_offline_progress(
+        &mut self,
+        now: u64,
+        settings: &OfflineProgressSettings,
+    ) -> Option<OfflineReport> {
+        let away_seconds = now.saturating_sub(self.last_saved?);
+        if away_seconds < settings.min_seconds {
+            return None;
+        }
+        let credited_seconds = away_seconds.min(settings.cap_seconds);
+        let queued = self.events.len();
+
+        let earnings: Vec<_> = self
+            .definitions
+            .automatons()
+            .map(|definition| definition.variant)
+            .filter_map(|variant| {
+                let earned = self.rate_per_second_by_source(variant)
+                    * credited_seconds as f64
+                    * settings.efficiency;
+                let earned = Currency::from_f64(earned).floor();
+                (!earned.is_zero()).then_some((variant, earned))
+            })
+            .collect();
+        for &(variant, earned) in &earnings {
+            self.credit(variant, earned);
+        }
+        // The report stands in for the income events, which would otherwise
+        // only go out once play starts.
+        self.events.truncate(queued);
+        if !earnings.is_empty() {
+            self.mark_dirty();
+        }
+
+        Some(OfflineReport {
+            away_seconds,
+            credited_seconds,
+            earnings,
+        })
+    }
+
+    /// Entropy generated over the life of this save, including amounts since spent.
+    pub fn total_generated(&self) -> Currency {
+        self.income_by_type.values().sum()
+    }
+
+    /// The furthest automaton along the unlock chain that is owned or purchasable.
+    pub fn highest_unlocked(&self) -> Option<AutomatonVariant> {
+        self.definitions
+            .automatons()
+            .map(|definition| definition.variant)
+            .filter(|&variant| {
+                self.get_quantity_owned_by_source(variant) > 0 || self.prerequisites_met(variant)
+            })
+            .last()
     }
 
     pub fn prerequisites_met(&self, source: AutomatonVariant) -> bool {
-        source.is_automaton() && source.definition().unlock_requirement.is_met(source, self)
+        source.is_automaton()
+            && self
+                .definitions
+                .source(source)
+                .unlock_requirement
+                .is_met(source, self)
     }
 
-    pub fn unmet_unlock_requirement(&self, source: AutomatonVariant) -> Option<UnlockRequirement> {
-        let requirement = source.definition().unlock_requirement;
+    pub fn unmet_unlock_requirement(&self, source: AutomatonVariant) -> Option<&UnlockRequirement> {
+        let requirement = &self.definitions.source(source).unlock_requirement;
         if requirement.is_met(source, self) {
             None
         } else {
@@ -487,23 +1130,91 @@ impl GameData {
     pub fn update_audio_settings(&mut self, volume: f32, play_pickup: bool) {
         self.audio_settings.volume = volume;
         self.audio_settings.play_pickup = play_pickup;
-        self.save();
+        self.mark_dirty();
     }
 
-    fn save(&self) {
-        let data = serde_json::to_string(self).unwrap();
-        #[cfg(target_arch = "wasm32")]
+    /// Checks invariants serde can't express, for data coming from outside
+    /// the game such as save files.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.audio_settings.volume) {
+            return Err(format!(
+                "volume {} is outside 0..=1",
+                self.audio_settings.volume
+            ));
+        }
+        let counted = self.owned_by_type.keys().chain(self.levels_by_type.keys());
+        if let Some(variant) = counted.into_iter().find(|variant| !variant.is_automaton()) {
+            return Err(format!("{variant:?} cannot be owned or levelled"));
+        }
+        if let Some((variant, owned)) = self
+            .owned_by_type
+            .iter()
+            .find(|(_, owned)| **owned > MAX_OWNED_PER_SOURCE)
         {
-            if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten())
-            {
-                let _ = storage.set_item("game_data", &data);
+            return Err(format!(
+                "{owned} {variant:?} is over the limit of {MAX_OWNED_PER_SOURCE}"
+            ));
+        }
+        if let Some(buff) = self.buffs.iter().find(|buff| {
+            !(buff.multiplier.is_finite() && buff.multiplier > 0.0 && buff.duration > 0.0)
+        }) {
+            return Err(format!("buff {} is out of range", buff.name));
+        }
+        for (variant, auto_buyer) in &self.auto_buyers {
+            let policy_ok = match auto_buyer.policy {
+                AutoBuyPolicy::MaxPayback(seconds) => seconds.is_finite() && seconds > 0.0,
+                _ => true,
+            };
+            if !(auto_buyer.interval.is_finite() && auto_buyer.interval > 0.0 && policy_ok) {
+                return Err(format!("the {variant:?} auto-buyer is out of range"));
             }
         }
+        Ok(())
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let _ = std::fs::write("save_data.json", data);
-        }
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn is_save_requested(&self) -> bool {
+        self.save_requested
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Asks for the next frame's flush to write, rather than waiting for the
+    /// save interval.
+    pub fn request_save(&mut self) {
+        self.dirty = true;
+        self.save_requested = true;
+    }
+
+    pub fn set_last_saved(&mut self, unix_time: u64) {
+        self.last_saved = Some(unix_time);
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+        self.save_requested = false;
+    }
+
+    /// Keeps the data dirty so the next scheduled flush retries, without
+    /// retrying an urgent save every frame.
+    pub fn mark_save_failed(&mut self) {
+        self.save_requested = false;
+    }
+}
+
+#[cfg(test)]
+impl GameData {
+    /// A fresh game with `owned` Hellmites bought and Entropy to spare.
+    pub fn with_hellmites(owned: u64) -> Self {
+        let mut data = Self::default();
+        data.add_income(AutomatonVariant::Portal, Currency::new(1_000_000));
+        assert!(data.purchase_sources(AutomatonVariant::Hellmite, owned));
+        data
     }
 }
 
@@ -511,24 +1222,10 @@ impl GameData {
 mod tests {
     use super::*;
 
-    #[test]
-    fn source_definitions_have_unique_variants() {
-        for definition in SOURCE_DEFINITIONS {
-            let count = SOURCE_DEFINITIONS
-                .iter()
-                .filter(|other| other.variant == definition.variant)
-                .count();
-            assert_eq!(
-                count, 1,
-                "{:?} is defined more than once",
-                definition.variant
-            );
-        }
-    }
-
     #[test]
     fn automaton_asset_paths_are_derived_from_asset_name() {
-        let hellmite = AutomatonVariant::Hellmite.definition();
+        let definitions = Definitions::default();
+        let hellmite = definitions.source(AutomatonVariant::Hellmite);
 
         assert_eq!(hellmite.asset_name(), Some("hellmite"));
         assert_eq!(
@@ -545,7 +1242,7 @@ mod tests {
         assert!(!game_data.prerequisites_met(AutomatonVariant::Hellmite));
         assert!(!game_data.prerequisites_met(AutomatonVariant::Abyssopod));
 
-        game_data.currency = 25;
+        game_data.currency = Currency::new(25);
         assert!(game_data.prerequisites_met(AutomatonVariant::Hellmite));
 
         game_data
@@ -554,4 +1251,481 @@ mod tests {
 
         assert!(game_data.prerequisites_met(AutomatonVariant::Abyssopod));
     }
+
+    #[test]
+    fn composite_requirements_are_evaluated_recursively() {
+        let requirement = UnlockRequirement::All(vec![
+            UnlockRequirement::PreviousAutomaton {
+                variant: AutomatonVariant::Hellmite,
+                quantity: 20,
+            },
+            UnlockRequirement::Any(vec![
+                UnlockRequirement::VariantLevel {
+                    variant: AutomatonVariant::Abyssopod,
+                    level: 2,
+                },
+                UnlockRequirement::PortalClicks(100),
+            ]),
+        ]);
+        let source = AutomatonVariant::GapingDubine;
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 20)]),
+            ..default()
+        };
+        assert!(!requirement.is_met(source, &game_data));
+
+        game_data.portal_clicks = 100;
+        assert!(requirement.is_met(source, &game_data));
+
+        game_data.portal_clicks = 0;
+        game_data
+            .levels_by_type
+            .insert(AutomatonVariant::Abyssopod, 2);
+        assert!(requirement.is_met(source, &game_data));
+
+        game_data.owned_by_type.clear();
+        assert!(!requirement.is_met(source, &game_data));
+        assert!(UnlockRequirement::LifetimeEntropy(Currency::ZERO).is_met(source, &game_data));
+        assert_eq!(
+            requirement.required_variants(),
+            [AutomatonVariant::Hellmite, AutomatonVariant::Abyssopod]
+        );
+    }
+
+    #[test]
+    fn level_ups_follow_the_cost_curve_up_to_the_cap() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(hellmite, 1_000)]),
+            ..default()
+        };
+        assert_eq!(game_data.cost_to_level_up(hellmite), Some(50));
+        assert!(game_data.level_up(hellmite));
+        assert_eq!(game_data.get_quantity_owned_by_source(hellmite), 950);
+        assert_eq!(game_data.cost_to_level_up(hellmite), Some(75));
+
+        game_data.levels_by_type.insert(hellmite, 4);
+        assert_eq!(game_data.cooldown(hellmite), 2.5);
+        assert!(game_data.level_up(hellmite));
+        assert_eq!(game_data.cooldown(hellmite), 1.25);
+
+        game_data.levels_by_type.insert(hellmite, 9);
+        assert!(game_data.level_up(hellmite));
+        assert_eq!(game_data.max_level(hellmite), Some(10));
+        assert_eq!(game_data.cost_to_level_up(hellmite), None);
+        assert!(!game_data.level_up(hellmite));
+        assert!(
+            (game_data.level_multiplier(hellmite) - LEVEL_MULTIPLIER_BASE.powi(10) * 2.0).abs()
+                < 1e-9
+        );
+
+        let table = LevelCostCurve::Table(vec![10, 20]);
+        assert_eq!(table.cost(1), Some(20));
+        assert_eq!(table.cost(2), None);
+    }
+
+    #[test]
+    fn costs_keep_growing_past_the_range_of_u64() {
+        let variant = AutomatonVariant::WoolyChionoescent;
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(variant, 44)]),
+            ..default()
+        };
+        let cost_at_44 = game_data.get_cost_to_add_source(variant);
+
+        game_data.owned_by_type.insert(variant, 200);
+        let cost_at_200 = game_data.get_cost_to_add_source(variant);
+
+        assert!(cost_at_44 > Currency::new(u64::MAX / 1_000));
+        assert!(cost_at_200 > cost_at_44);
+        assert!(cost_at_200 > Currency::new(u64::MAX));
+    }
+
+    #[test]
+    fn bulk_costs_match_buying_one_at_a_time() {
+        let variant = AutomatonVariant::Abyssopod;
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(variant, 3)]),
+            ..default()
+        };
+        let bulk = game_data.get_cost_to_add_sources(variant, 10).to_f64();
+
+        let mut singles = 0.0;
+        for _ in 0..10 {
+            let stats = game_data.definitions().source(variant).stats;
+            let owned = game_data.get_quantity_owned_by_source(variant);
+            singles += stats.base_cost.to_f64() * stats.ratio.powf(owned as f64);
+            *game_data.owned_by_type.entry(variant).or_default() += 1;
+        }
+
+        assert!((bulk - singles).abs() < 1.0, "{bulk} != {singles}");
+    }
+
+    #[test]
+    fn max_affordable_is_the_largest_count_within_budget() {
+        let variant = AutomatonVariant::Hellmite;
+        for currency in [0, 24, 25, 51, 1_000, 123_456_789] {
+            let game_data = GameData {
+                currency: Currency::new(currency),
+                owned_by_type: HashMap::from([(variant, 7)]),
+                ..default()
+            };
+            let count = game_data.max_affordable_sources(variant);
+
+            if count > 0 {
+                assert!(game_data.get_cost_to_add_sources(variant, count) <= game_data.currency);
+            }
+            assert!(game_data.get_cost_to_add_sources(variant, count + 1) > game_data.currency);
+        }
+    }
+
+    #[test]
+    fn owned_counts_stop_at_the_cap() {
+        let variant = AutomatonVariant::Hellmite;
+        let mut game_data = GameData {
+            currency: Currency::from_log10(1e12),
+            owned_by_type: HashMap::from([(variant, MAX_OWNED_PER_SOURCE - 1)]),
+            ..default()
+        };
+
+        assert!(!game_data.purchase_sources(variant, u64::MAX));
+        assert!(!game_data.purchase_sources(variant, 2));
+        assert_eq!(game_data.max_affordable_sources(variant), 1);
+        assert!(game_data.purchase_sources(variant, 1));
+        assert_eq!(game_data.max_affordable_sources(variant), 0);
+    }
+
+    #[test]
+    fn bulk_purchases_are_all_or_nothing() {
+        let variant = AutomatonVariant::Hellmite;
+        let mut game_data = GameData {
+            currency: Currency::new(1_000),
+            ..default()
+        };
+        let cost = game_data.get_cost_to_add_sources(variant, 10);
+
+        assert!(!game_data.purchase_sources(variant, 100));
+        assert_eq!(game_data.get_quantity_owned_by_source(variant), 0);
+
+        assert!(game_data.purchase_sources(variant, 10));
+        assert_eq!(game_data.get_quantity_owned_by_source(variant), 10);
+        assert_eq!(game_data.get_currency(), Currency::new(1_000) - cost);
+    }
+
+    #[test]
+    fn synergies_boost_their_target_from_the_source_count() {
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([
+                (AutomatonVariant::Hellmite, 10),
+                (AutomatonVariant::Lorgner, 5),
+                (AutomatonVariant::GapingDubine, 9),
+            ]),
+            ..default()
+        };
+        assert!(
+            (game_data.rate_per_second_by_source(AutomatonVariant::Hellmite) - 4.4).abs() < 1e-9
+        );
+        assert_eq!(game_data.cooldown(AutomatonVariant::Abyssopod), 7.5);
+
+        game_data
+            .owned_by_type
+            .insert(AutomatonVariant::GapingDubine, 10);
+        assert!((game_data.cooldown(AutomatonVariant::Abyssopod) - 6.75).abs() < 1e-6);
+        // Compounding per-owned reductions bottom out instead of reaching zero.
+        game_data
+            .owned_by_type
+            .insert(AutomatonVariant::Struthios, u64::MAX);
+        assert_eq!(game_data.cooldown(AutomatonVariant::Lorgner), MIN_COOLDOWN);
+    }
+
+    #[test]
+    fn portal_clicks_crit_and_share_the_automaton_rate() {
+        let mut game_data = GameData {
+            currency: Currency::new(100_000),
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 100)]),
+            ..default()
+        };
+        assert!(!game_data.click_portal(0.5));
+        assert!(game_data.click_portal(0.0));
+        assert_eq!(game_data.get_portal_clicks(), 2);
+        assert_eq!(game_data.get_portal_crits(), 1);
+        assert_eq!(game_data.get_best_click(), Currency::new(10));
+
+        for id in ["focused_gaze", "portal_resonance", "resonant_echo"] {
+            assert!(game_data.purchase_upgrade(id));
+        }
+        // 1 + 1 + 5 flat, plus 5% of 100 Hellmites' 40 Entropy/s.
+        assert_eq!(game_data.portal_click_amount(), Currency::new(7));
+        assert_eq!(game_data.portal_click_value(), Currency::new(9));
+        assert!(game_data.click_portal(0.01));
+        assert_eq!(game_data.get_best_click(), Currency::new(90));
+
+        // Lucidity doubles the flat part and the rate once each: 14 + 5% of 80.
+        game_data.lucidity = Currency::new(50);
+        assert_eq!(game_data.portal_click_value(), Currency::new(18));
+        assert!(!game_data.click_portal(0.5));
+        assert_eq!(
+            game_data.get_currency_by_source(AutomatonVariant::Portal),
+            Currency::new(1 + 10 + 90 + 18)
+        );
+    }
+
+    #[test]
+    fn banishing_refunds_half_of_the_last_prices() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut game_data = GameData {
+            currency: Currency::new(10_000),
+            ..default()
+        };
+        assert!(game_data.purchase_sources(hellmite, 10));
+        let spent = Currency::new(10_000) - game_data.get_currency();
+        assert!(!game_data.sell_source(hellmite, 11));
+
+        game_data.take_events();
+        let refund = game_data.refund_for_sources(hellmite, 1);
+        assert!(game_data.sell_source(hellmite, 1));
+        assert_eq!(
+            refund,
+            (game_data.get_cost_to_add_source(hellmite) * 0.5).floor()
+        );
+        assert_eq!(
+            game_data.take_events(),
+            [GameEvent::SourceBanished {
+                source: hellmite,
+                count: 1,
+                refund
+            }]
+        );
+
+        assert!(game_data.sell_source(hellmite, 9));
+        assert_eq!(game_data.get_quantity_owned_by_source(hellmite), 0);
+        let refunded = game_data.get_currency() - (Currency::new(10_000) - spent);
+        assert!((refunded.to_f64() - spent.to_f64() * 0.5).abs() <= 2.0);
+        assert_eq!(game_data.total_generated(), Currency::ZERO);
+    }
+
+    #[test]
+    fn prestige_trades_progress_for_a_lasting_multiplier() {
+        let mut game_data = GameData {
+            currency: Currency::new(5_000),
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 20)]),
+            levels_by_type: HashMap::from([(AutomatonVariant::Hellmite, 1)]),
+            income_by_type: HashMap::from([(
+                AutomatonVariant::Hellmite,
+                Currency::new(25_000_000),
+            )]),
+            ..default()
+        };
+        assert_eq!(game_data.pending_lucidity(), Currency::new(5));
+
+        assert!(game_data.prestige());
+
+        assert_eq!(game_data.get_lucidity(), Currency::new(5));
+        assert_eq!(game_data.get_prestige_count(), 1);
+        assert_eq!(game_data.get_currency(), Currency::ZERO);
+        assert_eq!(
+            game_data.get_quantity_owned_by_source(AutomatonVariant::Hellmite),
+            0
+        );
+        assert_eq!(game_data.get_level(AutomatonVariant::Hellmite), 0);
+        assert!(!game_data.can_prestige());
+        assert!(!game_data.prestige());
+
+        game_data.add_income(AutomatonVariant::Portal, Currency::new(100));
+        assert_eq!(game_data.get_currency(), Currency::new(110));
+
+        game_data
+            .owned_by_type
+            .insert(AutomatonVariant::Hellmite, 10);
+        assert!(
+            (game_data.rate_per_second_by_source(AutomatonVariant::Hellmite) - 4.4).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn upgrades_need_prerequisites_and_apply_their_effects() {
+        let mut game_data = GameData {
+            currency: Currency::new(100_000),
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 10)]),
+            ..default()
+        };
+        assert!(!game_data.purchase_upgrade("hardened_chitin"));
+        assert!(game_data.purchase_upgrade("barbed_claws"));
+        assert!(!game_data.purchase_upgrade("barbed_claws"));
+        assert!(game_data.is_save_requested());
+
+        game_data.add_income(AutomatonVariant::Hellmite, Currency::new(10));
+        assert_eq!(
+            game_data.get_currency_by_source(AutomatonVariant::Hellmite),
+            Currency::new(20)
+        );
+
+        assert!(game_data.purchase_upgrade("quickened_pulse"));
+        assert_eq!(game_data.cooldown(AutomatonVariant::Hellmite), 2.25);
+        assert!(
+            (game_data.rate_per_second_by_source(AutomatonVariant::Hellmite) - 20.0 / 2.25).abs()
+                < 1e-9
+        );
+
+        let before = game_data.get_cost_to_add_source(AutomatonVariant::Hellmite);
+        assert!(game_data.purchase_upgrade("bargaining_rites"));
+        assert!((game_data.cost_ratio(AutomatonVariant::Hellmite) - 1.045).abs() < 1e-12);
+        assert!(game_data.get_cost_to_add_source(AutomatonVariant::Hellmite) < before);
+
+        assert!(game_data.purchase_upgrade("focused_gaze"));
+        assert_eq!(game_data.portal_click_amount(), Currency::new(2));
+        assert_eq!(game_data.get_currency(), Currency::new(14_320));
+    }
+
+    #[test]
+    fn income_marks_data_dirty_without_requesting_a_save() {
+        let mut game_data = GameData::default();
+
+        for _ in 0..10_000 {
+            game_data.add_income(AutomatonVariant::Portal, Currency::new(1));
+        }
+
+        assert_eq!(game_data.get_currency(), Currency::new(10_000));
+        assert!(game_data.is_dirty());
+        assert!(!game_data.is_save_requested());
+    }
+
+    #[test]
+    fn purchases_request_an_immediate_save() {
+        let mut game_data = GameData {
+            currency: Currency::new(25),
+            ..default()
+        };
+
+        assert!(game_data.purchase_sources(AutomatonVariant::Hellmite, 1));
+        assert!(game_data.is_save_requested());
+
+        game_data.mark_saved();
+        assert!(!game_data.is_dirty());
+        assert!(!game_data.is_save_requested());
+    }
+
+    #[test]
+    fn offline_progress_is_capped_and_scaled_by_efficiency() {
+        let settings = OfflineProgressSettings {
+            min_seconds: 60,
+            cap_seconds: 1_000,
+            efficiency: 0.5,
+        };
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 20)]),
+            last_saved: Some(10_000),
+            ..default()
+        };
+
+        assert_eq!(game_data.apply_offline_progress(10_030, &settings), None);
+
+        // 20 Hellmites make 8 Entropy/s; five hours away is capped to 1,000s.
+        let report = game_data
+            .apply_offline_progress(10_000 + 5 * 60 * 60, &settings)
+            .expect("absence should be credited");
+
+        assert_eq!(report.credited_seconds, 1_000);
+        assert_eq!(
+            report.earnings,
+            vec![(AutomatonVariant::Hellmite, Currency::new(4_000))]
+        );
+        assert_eq!(game_data.get_currency(), Currency::new(4_000));
+        assert_eq!(
+            game_data.get_currency_by_source(AutomatonVariant::Hellmite),
+            Currency::new(4_000)
+        );
+        assert!(!game_data.has_events());
+    }
+
+    #[test]
+    fn saves_without_a_timestamp_earn_nothing_offline() {
+        let mut game_data = GameData {
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 20)]),
+            ..default()
+        };
+
+        let report =
+            game_data.apply_offline_progress(u64::MAX, &OfflineProgressSettings::default());
+
+        assert_eq!(report, None);
+        assert_eq!(game_data.get_currency(), Currency::ZERO);
+    }
+
+    #[test]
+    fn buffs_stack_by_rule_and_expire() {
+        let mut game_data = GameData::default();
+        let frenzy = || Buff::new("Frenzy", BuffEffect::Production, 7.0, 30.0);
+
+        game_data.add_buff(frenzy(), BuffStacking::Refresh);
+        game_data.tick_buffs(20.0);
+        game_data.add_buff(frenzy(), BuffStacking::Refresh);
+        assert_eq!(game_data.buffs().len(), 1);
+        assert_eq!(game_data.buffs()[0].remaining, 30.0);
+
+        game_data.add_buff(frenzy(), BuffStacking::Extend);
+        assert_eq!(game_data.buffs()[0].remaining, 60.0);
+
+        game_data.add_buff(frenzy(), BuffStacking::Stack);
+        assert_eq!(game_data.buff_multiplier(BuffEffect::Production), 49.0);
+        assert_eq!(game_data.buff_multiplier(BuffEffect::Clicks), 1.0);
+        assert_eq!(
+            game_data.add_income(AutomatonVariant::Hellmite, Currency::new(2)),
+            Currency::new(98)
+        );
+
+        game_data.tick_buffs(30.0);
+        assert_eq!(game_data.buffs().len(), 1);
+        game_data.tick_buffs(30.0);
+        assert!(game_data.buffs().is_empty());
+        assert_eq!(game_data.buff_multiplier(BuffEffect::Production), 1.0);
+    }
+
+    #[test]
+    fn auto_buyers_need_an_upgrade_and_respect_their_policy() {
+        let mut game_data = GameData {
+            currency: Currency::new(100_000),
+            owned_by_type: HashMap::from([(AutomatonVariant::Hellmite, 1)]),
+            ..default()
+        };
+        let hellmite = AutomatonVariant::Hellmite;
+        game_data.set_auto_buyer(
+            hellmite,
+            AutoBuyer {
+                enabled: true,
+                ..default()
+            },
+        );
+        assert!(!game_data.auto_buy(hellmite));
+
+        assert!(game_data.purchase_upgrade("barbed_claws"));
+        assert!(game_data.purchase_upgrade("restless_instinct"));
+        assert!(!game_data.auto_buyer_unlocked(AutomatonVariant::Abyssopod));
+        assert!(game_data.auto_buy(hellmite));
+        assert_eq!(game_data.get_quantity_owned_by_source(hellmite), 2);
+
+        let reserve = game_data.get_currency();
+        game_data.set_auto_buyer(
+            hellmite,
+            AutoBuyer {
+                enabled: true,
+                interval: 1.0,
+                policy: AutoBuyPolicy::KeepReserve(reserve),
+            },
+        );
+        assert!(!game_data.auto_buy(hellmite));
+
+        let payback = game_data.payback_seconds(hellmite).unwrap();
+        for (limit, bought) in [(payback * 0.5, false), (payback * 2.0, true)] {
+            game_data.set_auto_buyer(
+                hellmite,
+                AutoBuyer {
+                    enabled: true,
+                    interval: 1.0,
+                    policy: AutoBuyPolicy::MaxPayback(limit),
+                },
+            );
+            assert_eq!(game_data.auto_buy(hellmite), bought);
+        }
+    }
 }
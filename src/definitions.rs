@@ -0,0 +1,461 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use std::{
+    collections::HashSet,
+    marker::PhantomData,
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    achievements::{AchievementDefinition, AchievementDefinitions},
+    data::{
+        AutomatonVariant, GameData, LevelCostCurve, MIN_COOLDOWN, MilestoneBonus, SourceDefinition,
+    },
+    synergies::{Synergy, SynergyDefinitions},
+    upgrades::{UpgradeDefinition, UpgradeDefinitions},
+};
+
+/// A JSON file of game definitions under `assets/`, validated when it loads
+/// and hot-reloaded when edited.
+pub trait DefinitionFile: Asset + Clone + serde::de::DeserializeOwned {
+    const PATH: &'static str;
+    /// The copy of the file built into the binary, in effect until the asset
+    /// file loads.
+    const BUILTIN: &'static str;
+
+    /// Other assets the definitions refer to, checked for existence on load.
+    fn required_assets(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn validate(&self, asset_exists: &dyn Fn(&str) -> bool) -> Result<(), String>;
+
+    /// Swaps these in for the matching list in `definitions`.
+    fn apply(&self, definitions: &mut Definitions);
+}
+
+/// The game definitions in effect: the built-in copies until each asset file
+/// loads, then whatever it last held. Each list is swapped whole on reload,
+/// so clones stay cheap and anything holding one keeps a consistent copy.
+#[derive(Resource, Clone)]
+pub struct Definitions {
+    pub sources: Arc<[SourceDefinition]>,
+    pub upgrades: Arc<[UpgradeDefinition]>,
+    pub achievements: Arc<[AchievementDefinition]>,
+    pub synergies: Arc<[Synergy]>,
+}
+
+/// Parsed once, so every default `Definitions` shares the same lists.
+static BUILTIN: LazyLock<Definitions> = LazyLock::new(|| Definitions {
+    sources: builtin::<SourceDefinitions>().sources.into(),
+    upgrades: builtin::<UpgradeDefinitions>().upgrades.into(),
+    achievements: builtin::<AchievementDefinitions>().achievements.into(),
+    synergies: builtin::<SynergyDefinitions>().synergies.into(),
+});
+
+fn builtin<T: DefinitionFile>() -> T {
+    parse(T::BUILTIN, &|_| true)
+        .unwrap_or_else(|err| panic!("built-in {} is invalid: {err}", T::PATH))
+}
+
+impl Default for Definitions {
+    fn default() -> Self {
+        BUILTIN.clone()
+    }
+}
+
+impl Definitions {
+    pub fn source(&self, variant: AutomatonVariant) -> &SourceDefinition {
+        self.sources
+            .iter()
+            .find(|definition| definition.variant == variant)
+            .expect("missing source definition")
+    }
+
+    pub fn automatons(&self) -> impl Iterator<Item = &SourceDefinition> {
+        self.sources
+            .iter()
+            .filter(|definition| definition.is_automaton())
+    }
+
+    pub fn upgrade(&self, id: &str) -> Option<&UpgradeDefinition> {
+        self.upgrades.iter().find(|upgrade| upgrade.id == id)
+    }
+}
+
+/// Definitions are equal when they share the same lists, which is all a
+/// reload needs to check and keeps comparing game data cheap.
+impl PartialEq for Definitions {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.sources, &other.sources)
+            && Arc::ptr_eq(&self.upgrades, &other.upgrades)
+            && Arc::ptr_eq(&self.achievements, &other.achievements)
+            && Arc::ptr_eq(&self.synergies, &other.synergies)
+    }
+}
+
+impl std::fmt::Debug for Definitions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Definitions")
+            .field("sources", &self.sources.len())
+            .field("upgrades", &self.upgrades.len())
+            .field("achievements", &self.achievements.len())
+            .field("synergies", &self.synergies.len())
+            .finish()
+    }
+}
+
+pub struct DefinitionsPlugin;
+
+impl Plugin for DefinitionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            DefinitionFilePlugin::<SourceDefinitions>::default(),
+            DefinitionFilePlugin::<UpgradeDefinitions>::default(),
+            DefinitionFilePlugin::<AchievementDefinitions>::default(),
+            DefinitionFilePlugin::<SynergyDefinitions>::default(),
+        ));
+        app.add_systems(PreUpdate, share_definitions);
+    }
+}
+
+pub struct DefinitionFilePlugin<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for DefinitionFilePlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: DefinitionFile> Plugin for DefinitionFilePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Definitions>();
+        app.init_asset::<T>();
+        app.register_asset_loader(DefinitionLoader::<T>::default());
+        app.add_message::<DefinitionsChanged<T>>();
+        app.add_systems(Startup, load_definitions::<T>);
+        app.add_systems(PreUpdate, apply_definitions::<T>.before(share_definitions));
+    }
+}
+
+/// Written after edited definitions replace the ones in effect, for systems
+/// holding on to values derived from them.
+pub struct DefinitionsChanged<T>(PhantomData<fn() -> T>);
+
+impl<T: 'static> Message for DefinitionsChanged<T> {}
+
+#[derive(Resource)]
+struct DefinitionHandle<T: Asset>(Handle<T>);
+
+fn load_definitions<T: DefinitionFile>(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DefinitionHandle::<T>(asset_server.load(T::PATH)));
+}
+
+fn apply_definitions<T: DefinitionFile>(
+    mut events: MessageReader<AssetEvent<T>>,
+    handle: Option<Res<DefinitionHandle<T>>>,
+    assets: Res<Assets<T>>,
+    mut in_effect: ResMut<Definitions>,
+    mut changed: MessageWriter<DefinitionsChanged<T>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        if let Some(definitions) = assets.get(*id) {
+            definitions.apply(&mut in_effect);
+            info!("Applied definitions from {}", T::PATH);
+            changed.write(DefinitionsChanged(PhantomData));
+        }
+    }
+}
+
+/// Hands the definitions in effect to the game data, including data loaded
+/// since the last reload.
+fn share_definitions(definitions: Res<Definitions>, data: Option<ResMut<GameData>>) {
+    if let Some(mut data) = data
+        && data.definitions() != &*definitions
+    {
+        data.set_definitions(definitions.clone());
+    }
+}
+
+#[derive(Debug)]
+pub enum DefinitionsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl std::fmt::Display for DefinitionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinitionsError::Io(err) => write!(f, "could not read definitions: {err}"),
+            DefinitionsError::Parse(err) => write!(f, "definitions are not valid JSON: {err}"),
+            DefinitionsError::Invalid(reason) => write!(f, "definitions are invalid: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionsError {}
+
+#[derive(TypePath)]
+pub struct DefinitionLoader<T>(PhantomData<fn() -> T>);
+
+impl<T> Default for DefinitionLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: DefinitionFile> AssetLoader for DefinitionLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = DefinitionsError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<T, DefinitionsError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(DefinitionsError::Io)?;
+
+        let definitions: T = serde_json::from_slice(&bytes).map_err(DefinitionsError::Parse)?;
+        let mut present = HashSet::new();
+        for path in definitions.required_assets() {
+            if load_context.read_asset_bytes(path.clone()).await.is_ok() {
+                present.insert(path);
+            }
+        }
+        definitions
+            .validate(&|path| present.contains(path))
+            .map_err(DefinitionsError::Invalid)?;
+
+        Ok(definitions)
+    }
+}
+
+/// Parses and validates definitions that did not come through the asset
+/// server, such as the copies built into the binary.
+pub fn parse<T: DefinitionFile>(
+    contents: &str,
+    asset_exists: &dyn Fn(&str) -> bool,
+) -> Result<T, DefinitionsError> {
+    let definitions: T = serde_json::from_str(contents).map_err(DefinitionsError::Parse)?;
+    definitions
+        .validate(asset_exists)
+        .map_err(DefinitionsError::Invalid)?;
+    Ok(definitions)
+}
+
+/// Parses `T`'s built-in definitions after `edit` has changed the list the
+/// file holds, for testing validation. Models are checked against the files
+/// on disk.
+#[cfg(test)]
+pub fn parse_edited<T: DefinitionFile>(
+    edit: impl FnOnce(&mut Vec<serde_json::Value>),
+) -> Result<T, DefinitionsError> {
+    let mut document: serde_json::Value = serde_json::from_str(T::BUILTIN).unwrap();
+    let list = document
+        .as_object_mut()
+        .and_then(|fields| fields.values_mut().next())
+        .and_then(|list| list.as_array_mut())
+        .expect("definition files hold a single list");
+    edit(list);
+    parse(&document.to_string(), &|path| {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("assets")
+            .join(path)
+            .exists()
+    })
+}
+
+#[derive(Asset, TypePath, Debug, Clone, serde::Deserialize)]
+pub struct SourceDefinitions {
+    pub sources: Vec<SourceDefinition>,
+}
+
+impl DefinitionFile for SourceDefinitions {
+    const PATH: &'static str = "data/sources.json";
+    const BUILTIN: &'static str = include_str!("../assets/data/sources.json");
+
+    fn required_assets(&self) -> Vec<String> {
+        self.sources.iter().filter_map(|d| d.model_path()).collect()
+    }
+
+    /// Checks that every variant is defined exactly once, that automaton
+    /// models exist and stats are in range, and that unlock requirements
+    /// only refer to automatons and never loop.
+    fn validate(&self, asset_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        let definitions = &self.sources;
+        for variant in AutomatonVariant::ALL {
+            match definitions.iter().filter(|d| d.variant == variant).count() {
+                0 => return Err(format!("{variant:?} is not defined")),
+                1 => {}
+                _ => return Err(format!("{variant:?} is defined more than once")),
+            }
+        }
+
+        for definition in definitions {
+            let variant = definition.variant;
+            if definition.is_automaton() != (variant != AutomatonVariant::Portal) {
+                return Err(format!("{variant:?} has the wrong kind"));
+            }
+            if let Some(path) = definition.model_path()
+                && !asset_exists(&path)
+            {
+                return Err(format!("{variant:?} uses missing model {path}"));
+            }
+            let stats = &definition.stats;
+            if definition.is_automaton() && (stats.cooldown < MIN_COOLDOWN || stats.ratio < 1.0) {
+                return Err(format!(
+                    "{variant:?} needs a cooldown of at least {MIN_COOLDOWN}s and a ratio of at least 1"
+                ));
+            }
+            if definition.is_automaton() {
+                let Some(leveling) = &definition.leveling else {
+                    return Err(format!("{variant:?} has no leveling"));
+                };
+                let curve_ok = match &leveling.cost {
+                    LevelCostCurve::Linear { .. } => true,
+                    LevelCostCurve::Geometric { ratio, .. } => *ratio >= 1.0,
+                    LevelCostCurve::Table(costs) => !costs.is_empty(),
+                };
+                let bonuses_ok = leveling.milestones.iter().all(|m| match m.bonus {
+                    MilestoneBonus::ProductionMultiplier(multiplier) => multiplier > 0.0,
+                    MilestoneBonus::CooldownMultiplier(multiplier) => multiplier > 0.0,
+                });
+                if !curve_ok || !bonuses_ok {
+                    return Err(format!("{variant:?} has invalid leveling"));
+                }
+            }
+        }
+
+        let required_by = |variant: AutomatonVariant| {
+            definitions
+                .iter()
+                .find(|d| d.variant == variant)
+                .map(|d| d.unlock_requirement.required_variants())
+                .unwrap_or_default()
+        };
+        for start in AutomatonVariant::ALL {
+            let mut pending = required_by(start);
+            let mut seen = Vec::new();
+            while let Some(variant) = pending.pop() {
+                if variant == start {
+                    return Err(format!("the unlock chain from {start:?} loops"));
+                }
+                if variant == AutomatonVariant::Portal {
+                    return Err(format!(
+                        "{start:?} requires {variant:?}, which is not an automaton"
+                    ));
+                }
+                if !seen.contains(&variant) {
+                    seen.push(variant);
+                    pending.extend(required_by(variant));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, definitions: &mut Definitions) {
+        definitions.sources = self.sources.as_slice().into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_definitions_are_valid_and_models_exist() {
+        let definitions = parse_edited::<SourceDefinitions>(|_| {}).unwrap();
+
+        assert_eq!(definitions.sources.len(), AutomatonVariant::ALL.len());
+    }
+
+    #[test]
+    fn duplicate_variants_are_rejected() {
+        let result = parse_edited::<SourceDefinitions>(|sources| {
+            let duplicate = sources[0].clone();
+            sources.push(duplicate);
+        });
+
+        assert!(
+            matches!(result, Err(DefinitionsError::Invalid(reason)) if reason.contains("more than once"))
+        );
+    }
+
+    #[test]
+    fn missing_models_are_rejected() {
+        let result = parse_edited::<SourceDefinitions>(|sources| {
+            sources[0]["kind"]["Automaton"]["asset_name"] = "not_a_model".into();
+        });
+
+        assert!(
+            matches!(result, Err(DefinitionsError::Invalid(reason)) if reason.contains("not_a_model"))
+        );
+    }
+
+    #[test]
+    fn circular_unlock_chains_are_rejected() {
+        let result = parse_edited::<SourceDefinitions>(|sources| {
+            sources[0]["unlock_requirement"] = serde_json::json!({ "PreviousAutomaton": { "variant": "Abyssopod", "quantity": 1 } });
+        });
+
+        assert!(
+            matches!(result, Err(DefinitionsError::Invalid(reason)) if reason.contains("loops"))
+        );
+
+        let nested = parse_edited::<SourceDefinitions>(|sources| {
+            sources[0]["unlock_requirement"] = serde_json::json!({ "Any": [
+                { "LifetimeEntropy": 10 },
+                { "All": [{ "VariantLevel": { "variant": "Lorgner", "level": 1 } }] },
+            ] });
+        });
+
+        assert!(
+            matches!(nested, Err(DefinitionsError::Invalid(reason)) if reason.contains("loops"))
+        );
+    }
+
+    #[test]
+    fn reloads_swap_one_list_and_reach_the_game_data() {
+        let builtin = Definitions::default();
+        let sources = parse_edited::<SourceDefinitions>(|sources| {
+            sources[0]["display_name"] = "Renamed".into();
+        })
+        .unwrap();
+        let mut app = App::new();
+        app.init_resource::<Definitions>();
+        app.init_resource::<GameData>();
+        app.add_systems(Update, share_definitions);
+
+        sources.apply(&mut app.world_mut().resource_mut::<Definitions>());
+        app.update();
+
+        let data = app.world().resource::<GameData>();
+        let hellmite = data.definitions().source(AutomatonVariant::Hellmite);
+        assert_eq!(hellmite.display_name, "Renamed");
+        assert_eq!(
+            builtin.source(AutomatonVariant::Hellmite).display_name,
+            "Hellmite"
+        );
+        assert!(Arc::ptr_eq(&builtin.upgrades, &data.definitions().upgrades));
+    }
+}
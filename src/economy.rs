@@ -0,0 +1,465 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use crate::{
+    AppState,
+    buffs::Buff,
+    currency::Currency,
+    data::{AutomatonVariant, GameData, GameEvent},
+    production::ProductionTicks,
+};
+
+/// Economy ticks per second unless configured otherwise.
+pub const DEFAULT_TICKS_PER_SECOND: f64 = 20.0;
+
+/// Runs everything that changes the economy over time (income, buffs and
+/// auto-buyers) on the fixed timestep, so progress doesn't depend on frame
+/// rate. Needs no rendering, so it also runs under `MinimalPlugins`.
+pub struct EconomyPlugin {
+    pub ticks_per_second: f64,
+}
+
+impl Default for EconomyPlugin {
+    fn default() -> Self {
+        Self {
+            ticks_per_second: DEFAULT_TICKS_PER_SECOND,
+        }
+    }
+}
+
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct SourcePurchased {
+    pub source: AutomatonVariant,
+    pub count: u64,
+}
+
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct SourceLeveledUp {
+    pub source: AutomatonVariant,
+    pub level: u32,
+}
+
+/// `count` of `source` were banished for `refund` Entropy.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct SourceBanished {
+    pub source: AutomatonVariant,
+    pub count: u64,
+    pub refund: Currency,
+}
+
+/// Entropy credited to `source`, after multipliers.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct IncomeEarned {
+    pub source: AutomatonVariant,
+    pub amount: Currency,
+}
+
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct UpgradePurchased {
+    pub id: String,
+}
+
+/// `buff` was granted, before stacking with any active buff of its name.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct BuffAdded {
+    pub buff: Buff,
+}
+
+/// `source` can now be purchased, having been locked before.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct UnlockReached {
+    pub source: AutomatonVariant,
+}
+
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct PortalClicked {
+    pub amount: Currency,
+    pub crit: bool,
+}
+
+/// The dreamer woke up for the `count`th time, trading progress for Lucidity.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct WokeUp {
+    pub count: u32,
+}
+
+#[derive(SystemParam)]
+struct GameMessageWriters<'w> {
+    purchased: MessageWriter<'w, SourcePurchased>,
+    leveled_up: MessageWriter<'w, SourceLeveledUp>,
+    banished: MessageWriter<'w, SourceBanished>,
+    income: MessageWriter<'w, IncomeEarned>,
+    upgrades: MessageWriter<'w, UpgradePurchased>,
+    buffs: MessageWriter<'w, BuffAdded>,
+    unlocks: MessageWriter<'w, UnlockReached>,
+    portal_clicks: MessageWriter<'w, PortalClicked>,
+    wake_ups: MessageWriter<'w, WokeUp>,
+}
+
+/// Reads every message announcing progress, for systems that only need to
+/// know that something happened.
+#[derive(SystemParam)]
+pub struct ProgressMessages<'w, 's> {
+    purchased: MessageReader<'w, 's, SourcePurchased>,
+    leveled_up: MessageReader<'w, 's, SourceLeveledUp>,
+    income: MessageReader<'w, 's, IncomeEarned>,
+    portal_clicks: MessageReader<'w, 's, PortalClicked>,
+    wake_ups: MessageReader<'w, 's, WokeUp>,
+}
+
+impl ProgressMessages<'_, '_> {
+    /// Whether any progress was announced since the last call.
+    pub fn any(&mut self) -> bool {
+        let counts = [
+            self.purchased.read().count(),
+            self.leveled_up.read().count(),
+            self.income.read().count(),
+            self.portal_clicks.read().count(),
+            self.wake_ups.read().count(),
+        ];
+        counts.iter().any(|&count| count > 0)
+    }
+}
+
+/// Automatons that were purchasable when last checked, so only new unlocks
+/// are announced. Cleared on leaving `Playing`, as another profile may load.
+#[derive(Resource, Default)]
+struct KnownUnlocks(Option<HashSet<AutomatonVariant>>);
+
+/// The economy systems, in the order they run each tick.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EconomySystems;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(self.ticks_per_second));
+        app.init_resource::<ProductionTicks>();
+        app.configure_sets(
+            FixedUpdate,
+            EconomySystems.run_if(in_state(AppState::Playing)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (produce, tick_buffs, run_auto_buyers)
+                .chain()
+                .in_set(EconomySystems),
+        );
+
+        app.add_message::<SourcePurchased>()
+            .add_message::<SourceLeveledUp>()
+            .add_message::<SourceBanished>()
+            .add_message::<IncomeEarned>()
+            .add_message::<UpgradePurchased>()
+            .add_message::<BuffAdded>()
+            .add_message::<UnlockReached>()
+            .add_message::<PortalClicked>()
+            .add_message::<WokeUp>();
+        app.init_resource::<KnownUnlocks>();
+        app.add_systems(OnExit(AppState::Playing), forget_known_unlocks);
+        // After `Update`, so changes made by input handlers go out the same frame.
+        app.add_systems(
+            PostUpdate,
+            send_game_messages.run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Sends the events `GameData` queued as messages, and announces automatons
+/// that became purchasable.
+/// Only runs while playing, so events queued before then go out on the first
+/// frame of play. Offline progress drops its own, as its report covers them.
+fn send_game_messages(
+    mut data: ResMut<GameData>,
+    mut writers: GameMessageWriters,
+    mut known: ResMut<KnownUnlocks>,
+) {
+    if data.has_events() {
+        // Taking the queue is bookkeeping, not a change anyone should react to.
+        for event in data.bypass_change_detection().take_events() {
+            match event {
+                GameEvent::SourcePurchased { source, count } => {
+                    writers.purchased.write(SourcePurchased { source, count });
+                }
+                GameEvent::SourceLeveledUp { source, level } => {
+                    writers.leveled_up.write(SourceLeveledUp { source, level });
+                }
+                GameEvent::SourceBanished {
+                    source,
+                    count,
+                    refund,
+                } => {
+                    writers.banished.write(SourceBanished {
+                        source,
+                        count,
+                        refund,
+                    });
+                }
+                GameEvent::IncomeEarned { source, amount } => {
+                    writers.income.write(IncomeEarned { source, amount });
+                }
+                GameEvent::UpgradePurchased { id } => {
+                    writers.upgrades.write(UpgradePurchased { id });
+                }
+                GameEvent::BuffAdded { buff } => {
+                    writers.buffs.write(BuffAdded { buff });
+                }
+                GameEvent::PortalClicked { amount, crit } => {
+                    writers.portal_clicks.write(PortalClicked { amount, crit });
+                }
+                GameEvent::WokeUp { count } => {
+                    writers.wake_ups.write(WokeUp { count });
+                }
+            }
+        }
+    }
+
+    if !data.is_changed() {
+        return;
+    }
+    let now_unlocked: HashSet<_> = data
+        .definitions()
+        .automatons()
+        .map(|definition| definition.variant)
+        .filter(|&variant| data.prerequisites_met(variant))
+        .collect();
+    // What was unlocked when the profile loaded is not news.
+    if let Some(before) = known.0.as_ref() {
+        let reached = data
+            .definitions()
+            .automatons()
+            .map(|definition| definition.variant)
+            .filter(|variant| now_unlocked.contains(variant) && !before.contains(variant));
+        for source in reached {
+            writers.unlocks.write(UnlockReached { source });
+        }
+    }
+    known.0 = Some(now_unlocked);
+}
+
+fn forget_known_unlocks(mut known: ResMut<KnownUnlocks>) {
+    known.0 = None;
+}
+
+fn produce(time: Res<Time>, mut ticks: ResMut<ProductionTicks>, mut data: ResMut<GameData>) {
+    ticks.advance(&mut data, time.delta_secs_f64());
+}
+
+fn tick_buffs(time: Res<Time>, mut data: ResMut<GameData>) {
+    if data.buffs().is_empty() {
+        return;
+    }
+    data.tick_buffs(time.delta_secs());
+}
+
+/// Gives each enabled auto-buyer a purchase attempt every `interval` seconds.
+fn run_auto_buyers(
+    time: Res<Time>,
+    mut data: ResMut<GameData>,
+    mut since_attempt: Local<HashMap<AutomatonVariant, Duration>>,
+) {
+    let definitions = data.definitions().clone();
+    for definition in definitions.automatons() {
+        let variant = definition.variant;
+        let auto_buyer = data.auto_buyer(variant);
+        if !auto_buyer.enabled || !data.auto_buyer_unlocked(variant) {
+            since_attempt.remove(&variant);
+            continue;
+        }
+        let elapsed = since_attempt.entry(variant).or_default();
+        *elapsed += time.delta();
+        if elapsed.as_secs_f32() >= auto_buyer.interval {
+            *elapsed = Duration::ZERO;
+            data.auto_buy(variant);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffs::{Buff, BuffEffect, BuffStacking},
+        currency::Currency,
+        data::AutoBuyer,
+    };
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+    /// A headless app running only the economy, advanced one tick per update.
+    struct Harness {
+        app: App,
+    }
+
+    impl Harness {
+        fn new(data: GameData) -> Self {
+            let plugin = EconomyPlugin::default();
+            let tick = Duration::from_secs_f64(1.0 / plugin.ticks_per_second);
+            let mut app = App::new();
+            app.add_plugins((MinimalPlugins, StatesPlugin, plugin));
+            app.insert_state(AppState::Playing);
+            app.insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+            app.insert_resource(data);
+            // The first update only starts the clock.
+            app.update();
+            Self { app }
+        }
+
+        fn data_mut(&mut self) -> Mut<'_, GameData> {
+            self.app.world_mut().resource_mut::<GameData>()
+        }
+
+        /// Messages of type `M` sent during the last update.
+        fn messages<M: Message + Clone>(&self) -> Vec<M> {
+            self.app
+                .world()
+                .resource::<Messages<M>>()
+                .iter_current_update_messages()
+                .cloned()
+                .collect()
+        }
+
+        fn advance(&mut self, ticks: u32) -> &GameData {
+            for _ in 0..ticks {
+                self.app.update();
+            }
+            self.app.world().resource::<GameData>()
+        }
+    }
+
+    #[test]
+    fn income_is_exact_per_tick() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let data = GameData::with_hellmites(4);
+        // Hellmites tick every 2.5 s, so 4 of them earn 8 ticks in 5 s.
+        assert_eq!(data.cooldown(hellmite), 2.5);
+        let mut harness = Harness::new(data);
+
+        let data = harness.advance(100);
+
+        assert_eq!(
+            data.get_currency_by_source(hellmite),
+            data.definitions().source(hellmite).stats.currency_per_tick * 8.0
+        );
+    }
+
+    #[test]
+    fn buffs_run_out_on_the_tick() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(4);
+        data.add_buff(
+            Buff::new("Surge", BuffEffect::Production, 2.0, 2.5),
+            BuffStacking::Refresh,
+        );
+        let mut harness = Harness::new(data);
+
+        let data = harness.advance(51);
+        assert!(data.buffs().is_empty());
+        // Four ticks doubled, then four at the normal rate.
+        let data = harness.advance(49);
+        assert_eq!(
+            data.get_currency_by_source(hellmite),
+            data.definitions().source(hellmite).stats.currency_per_tick * 12.0
+        );
+    }
+
+    #[test]
+    fn changes_are_announced_as_messages() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(19);
+        // Setting up the save is not what's under test.
+        data.take_events();
+        let mut harness = Harness::new(data);
+
+        assert!(harness.data_mut().purchase_sources(hellmite, 1));
+        harness.data_mut().click_portal(1.0);
+        assert!(harness.data_mut().purchase_upgrade("focused_gaze"));
+        let frenzy = Buff::new("Frenzy", BuffEffect::Production, 2.0, 30.0);
+        harness
+            .data_mut()
+            .add_buff(frenzy.clone(), BuffStacking::Refresh);
+        harness.advance(1);
+
+        assert_eq!(
+            harness.messages::<SourcePurchased>(),
+            [SourcePurchased {
+                source: hellmite,
+                count: 1
+            }]
+        );
+        assert_eq!(
+            harness.messages::<UpgradePurchased>(),
+            [UpgradePurchased {
+                id: "focused_gaze".into()
+            }]
+        );
+        assert_eq!(
+            harness.messages::<BuffAdded>(),
+            [BuffAdded { buff: frenzy }]
+        );
+        assert_eq!(
+            harness.messages::<UnlockReached>(),
+            [UnlockReached {
+                source: AutomatonVariant::Abyssopod
+            }]
+        );
+        assert_eq!(
+            harness.messages::<PortalClicked>(),
+            [PortalClicked {
+                amount: Currency::new(1),
+                crit: false
+            }]
+        );
+        assert_eq!(
+            harness.messages::<IncomeEarned>(),
+            [IncomeEarned {
+                source: AutomatonVariant::Portal,
+                amount: Currency::new(1)
+            }]
+        );
+    }
+
+    #[test]
+    fn switching_profiles_does_not_announce_existing_unlocks() {
+        let mut harness = Harness::new(GameData::default());
+        harness.advance(1);
+
+        let mut data = GameData::with_hellmites(20);
+        data.take_events();
+        let app = &mut harness.app;
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::ProfileSelect);
+        app.update();
+        app.insert_resource(data);
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Playing);
+        app.update();
+
+        assert!(harness.messages::<UnlockReached>().is_empty());
+    }
+
+    #[test]
+    fn auto_buyers_buy_on_their_interval() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(1);
+        assert!(data.purchase_upgrade("barbed_claws"));
+        assert!(data.purchase_upgrade("restless_instinct"));
+        data.set_auto_buyer(
+            hellmite,
+            AutoBuyer {
+                enabled: true,
+                interval: 1.0,
+                ..default()
+            },
+        );
+        let mut harness = Harness::new(data);
+
+        let data = harness.advance(59);
+        assert_eq!(data.get_quantity_owned_by_source(hellmite), 3);
+        let data = harness.advance(1);
+        assert_eq!(data.get_quantity_owned_by_source(hellmite), 4);
+    }
+}
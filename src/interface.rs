@@ -1,6 +1,17 @@
 use crate::{
+    AppState,
     audio::AudioState,
-    data::{AutomatonVariant, GameData, UnlockRequirement},
+    clipboard,
+    currency::Currency,
+    data::{
+        AutoBuyPolicy, AutomatonVariant, CRIT_MULTIPLIER, GameData, OfflineProgressSettings,
+        OfflineReport, UnlockRequirement,
+    },
+    definitions::Definitions,
+    save::{self, SaveFailed, SaveRecovery},
+    storage::{self, SaveStore},
+    synergies::active_synergies,
+    upgrades::UpgradeEffect,
 };
 use bevy::{
     color::palettes::css::WHITE,
@@ -20,23 +31,26 @@ use bevy::{
 };
 
 static SCORE_FONT_SIZE: f32 = 32.0;
-static CONTROL_TITLE_FONT_SIZE: f32 = 22.0;
-static CONTROL_TEXT_FONT_SIZE: f32 = 18.0;
+pub static CONTROL_TITLE_FONT_SIZE: f32 = 22.0;
+pub static CONTROL_TEXT_FONT_SIZE: f32 = 18.0;
 static VARIANT_PANEL_BUTTON_FONT_SIZE: f32 = 14.0;
 static VARIANT_PANEL_STAT_FONT_SIZE: f32 = 14.0;
 const VARIANT_PANEL_STAT_WIDTH: f32 = 110.0;
-static FONT_PATH: &str = "fonts/Squada_One/SquadaOne-Regular.ttf";
-
-const PANEL_BACKGROUND: Color = Color::srgba(0.03, 0.02, 0.04, 1.0);
-const PANEL_BORDER: Color = Color::srgba(0.72, 0.62, 0.95, 0.45);
-const CONTROL_TEXT: Color = Color::srgb(0.94, 0.91, 1.0);
-const CONTROL_MUTED_TEXT: Color = Color::srgb(0.72, 0.68, 0.78);
-const CONTROL_TRACK: Color = Color::srgba(0.12, 0.10, 0.16, 0.92);
-const CONTROL_ACCENT: Color = Color::srgb(0.76, 0.38, 0.86);
-const CONTROL_ACCENT_HOVERED: Color = Color::srgb(0.94, 0.52, 0.88);
+pub static FONT_PATH: &str = "fonts/Squada_One/SquadaOne-Regular.ttf";
+
+pub const PANEL_BACKGROUND: Color = Color::srgba(0.03, 0.02, 0.04, 1.0);
+pub const PANEL_BORDER: Color = Color::srgba(0.72, 0.62, 0.95, 0.45);
+pub const CONTROL_TEXT: Color = Color::srgb(0.94, 0.91, 1.0);
+pub const CONTROL_MUTED_TEXT: Color = Color::srgb(0.72, 0.68, 0.78);
+pub const CONTROL_TRACK: Color = Color::srgba(0.12, 0.10, 0.16, 0.92);
+pub const CONTROL_ACCENT: Color = Color::srgb(0.76, 0.38, 0.86);
+pub const CONTROL_ACCENT_HOVERED: Color = Color::srgb(0.94, 0.52, 0.88);
 const CHECKBOX_BORDER: Color = Color::srgb(0.55, 0.50, 0.62);
 const CHECKBOX_BORDER_HOVERED: Color = Color::srgb(0.74, 0.70, 0.82);
 const CLEAR: Color = Color::srgba(0.0, 0.0, 0.0, 0.0);
+const WARNING_TEXT: Color = Color::srgb(1.0, 0.55, 0.45);
+/// Seconds a save failure notice stays on screen after the latest failure.
+const SAVE_NOTICE_DURATION: f32 = 8.0;
 
 pub struct InterfacePlugin;
 
@@ -49,18 +63,30 @@ impl Plugin for InterfacePlugin {
             TabNavigationPlugin,
         ));
         app.insert_resource(InterfaceState::default());
-        app.add_systems(Startup, setup.after(crate::audio::start_background_audio));
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            setup.after(crate::audio::start_background_audio),
+        );
         app.add_systems(
             Update,
             (
                 update_score,
                 update_variant_panel,
+                update_buy_amount_toggle,
+                update_auto_buyer_controls,
+                update_banish_hold,
                 sync_audio_controls,
                 update_music_volume_slider_style,
                 update_interaction_sound_checkbox_style,
                 update_audio_panel_visibility,
-            ),
+                show_save_failures,
+                update_recovery_dialog,
+                show_offline_summary,
+                show_import_preview,
+            )
+                .run_if(in_state(AppState::Playing)),
         );
+        app.add_systems(Update, update_dialog_button_style);
     }
 }
 
@@ -70,6 +96,55 @@ pub struct InterfaceState {
     /// Currently-selected variant. Set when a player clicks a purchase ring;
     /// cleared by the close button or by clicking outside the panel.
     pub selected_automaton: Option<AutomatonVariant>,
+    /// How many automatons the Summon button buys per click.
+    pub buy_amount: BuyAmount,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BuyAmount {
+    #[default]
+    One,
+    Ten,
+    Hundred,
+    Max,
+}
+
+impl BuyAmount {
+    const ALL: [BuyAmount; 4] = [
+        BuyAmount::One,
+        BuyAmount::Ten,
+        BuyAmount::Hundred,
+        BuyAmount::Max,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            BuyAmount::One => "x1",
+            BuyAmount::Ten => "x10",
+            BuyAmount::Hundred => "x100",
+            BuyAmount::Max => "Max",
+        }
+    }
+
+    /// Number of `source` a click buys. Max falls back to one when nothing is
+    /// affordable, so the button can still show the next price.
+    fn count(self, data: &GameData, source: AutomatonVariant) -> u64 {
+        match self {
+            BuyAmount::One => 1,
+            BuyAmount::Ten => 10,
+            BuyAmount::Hundred => 100,
+            BuyAmount::Max => data.max_affordable_sources(source).max(1),
+        }
+    }
+
+    /// Number of `source` the Banish button removes, capped at those owned.
+    fn banish_count(self, data: &GameData, source: AutomatonVariant) -> u64 {
+        let owned = data.get_quantity_owned_by_source(source);
+        match self {
+            BuyAmount::Max => owned,
+            _ => self.count(data, source).min(owned),
+        }
+    }
 }
 
 pub fn set_hovered_automaton<E: EntityEvent>(
@@ -95,17 +170,105 @@ enum VariantStat {
     Generated,
     Rate,
     Level,
+    /// Synergies from other variants affecting this one, one per line.
+    Synergies,
+    /// Best click, crit chance and owned upgrades, for the Portal only.
+    PortalDetails,
 }
 
 #[derive(Component, Clone, Copy)]
 enum VariantPanelButton {
     Summon,
     LevelUp,
+    Banish,
+}
+
+/// Seconds the Banish button has to be held before it acts.
+const BANISH_HOLD_SECONDS: f32 = 1.2;
+
+/// Progress of a press on a button that only acts once held long enough.
+#[derive(Component, Default)]
+struct HoldToConfirm {
+    held: bool,
+    progress: f32,
 }
 
+/// Fills a `HoldToConfirm` button from the left as it is held.
+#[derive(Component)]
+struct HoldProgressFill;
+
 #[derive(Component)]
 struct VariantPanelButtonLabel;
 
+#[derive(Component)]
+struct BuyAmountToggle;
+
+#[derive(Component, Clone, Copy)]
+struct BuyAmountButton(BuyAmount);
+
+/// Row of auto-buyer settings, shown once the selected variant's auto-buyer
+/// is unlocked.
+#[derive(Component)]
+struct AutoBuyerControls;
+
+/// An auto-buyer setting; clicking it flips or cycles to the next preset.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum AutoBuyerButton {
+    Toggle,
+    Interval,
+    Policy,
+}
+
+#[derive(Component)]
+struct AutoBuyerLabel(AutoBuyerButton);
+
+const AUTO_BUY_INTERVALS: [f32; 4] = [1.0, 5.0, 15.0, 60.0];
+
+const AUTO_BUY_POLICIES: [AutoBuyPolicy; 7] = [
+    AutoBuyPolicy::Always,
+    AutoBuyPolicy::KeepReserve(Currency::new(1_000)),
+    AutoBuyPolicy::KeepReserve(Currency::new(1_000_000)),
+    AutoBuyPolicy::KeepReserve(Currency::new(1_000_000_000)),
+    AutoBuyPolicy::MaxPayback(60.0),
+    AutoBuyPolicy::MaxPayback(600.0),
+    AutoBuyPolicy::MaxPayback(3600.0),
+];
+
+#[derive(Component)]
+struct SaveNotice;
+
+#[derive(Component)]
+struct DialogButton;
+
+#[derive(Component)]
+struct RecoveryDialog;
+
+#[derive(Component)]
+struct RecoveryDialogMessage;
+
+#[derive(Component)]
+struct OfflineSummary;
+
+#[derive(Component)]
+struct SaveCodeStatus;
+
+#[derive(Component)]
+struct ImportPreviewDialog;
+
+/// A save decoded from a pasted code, waiting for the player to confirm it
+/// should replace the current progress.
+#[derive(Resource)]
+struct ImportPreview {
+    incoming: GameData,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum RecoveryDialogButton {
+    StartFresh,
+    LoadBackup,
+    Quit,
+}
+
 #[derive(Component)]
 struct AudioControlPanel;
 
@@ -125,7 +288,7 @@ struct AudioPanelAnim {
 
 const AUDIO_PANEL_COLLAPSED_SIZE: f32 = 32.0;
 const AUDIO_PANEL_EXPANDED_WIDTH: f32 = 286.0;
-const AUDIO_PANEL_EXPANDED_HEIGHT: f32 = 110.0;
+const AUDIO_PANEL_EXPANDED_HEIGHT: f32 = 186.0;
 const AUDIO_PANEL_ANIM_SPEED: f32 = 6.0;
 
 #[derive(Component, Default)]
@@ -204,6 +367,59 @@ type CollapsedAudioPanelQuery<'w, 's> = Query<
     ),
 >;
 
+type VariantPanelStatQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static VariantPanelStat, &'static mut Text),
+    (Without<VariantPanelTitle>, Without<VariantPanelButtonLabel>),
+>;
+
+type VariantPanelButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static VariantPanelButton,
+        &'static Hovered,
+        &'static mut Node,
+        &'static mut BackgroundColor,
+        &'static mut BorderColor,
+        &'static Children,
+    ),
+    Without<VariantPanel>,
+>;
+
+type VariantPanelButtonLabelQuery<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Text,
+    (
+        With<VariantPanelButtonLabel>,
+        Without<VariantPanelTitle>,
+        Without<VariantPanelStat>,
+    ),
+>;
+
+type DialogButtonStyleQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Hovered,
+        &'static mut BackgroundColor,
+        &'static mut BorderColor,
+    ),
+    (With<DialogButton>, Changed<Hovered>),
+>;
+
+#[derive(SystemParam)]
+struct VariantPanelQueries<'w, 's> {
+    panels: Query<'w, 's, &'static mut Node, With<VariantPanel>>,
+    titles: Query<'w, 's, &'static mut Text, With<VariantPanelTitle>>,
+    stats: VariantPanelStatQuery<'w, 's>,
+    buttons: VariantPanelButtonQuery<'w, 's>,
+    button_labels: VariantPanelButtonLabelQuery<'w, 's>,
+    label_colors: Query<'w, 's, &'static mut TextColor, With<VariantPanelButtonLabel>>,
+}
+
 #[derive(SystemParam)]
 struct AudioPanelVisibilityQueries<'w, 's> {
     windows: Query<'w, 's, &'static Window>,
@@ -215,7 +431,34 @@ struct AudioPanelVisibilityQueries<'w, 's> {
     gear_icons: Query<'w, 's, &'static mut ImageNode, With<AudioControlPanelGearIcon>>,
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>, audio_state: Res<AudioState>) {
+/// Row of panel toggles in the top right corner. Plugins with a panel add
+/// their button to it in a system ordered after `setup`.
+#[derive(Component)]
+pub struct HudMenu;
+
+/// A panel opened from the `HudMenu`. Only one is open at a time.
+#[derive(Component)]
+pub struct HudPanel;
+
+/// Opens the `HudPanel` marked with `T` and closes the others, or closes it if
+/// it is already open.
+pub fn toggle_hud_panel<T: Component>(
+    on: On<Pointer<Click>>,
+    mut panels: Query<(&mut Node, Has<T>), With<HudPanel>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    for (mut node, is_target) in &mut panels {
+        node.display = if is_target && node.display == Display::None {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, audio_state: Res<AudioState>) {
     let font_handle = asset_server.load(FONT_PATH);
     let gear_icon: Handle<Image> = asset_server.load("icons/gear.png");
 
@@ -239,7 +482,33 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, audio_state: Re
         )],
     ));
 
+    commands.spawn((
+        HudMenu,
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(12),
+            top: px(12),
+            flex_direction: FlexDirection::Row,
+            column_gap: px(8),
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        SaveNotice,
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(12),
+            bottom: px(12),
+            ..default()
+        },
+        Visibility::Hidden,
+        Pickable::IGNORE,
+        control_text(&font_handle, "", CONTROL_TEXT_FONT_SIZE, WARNING_TEXT),
+    ));
+
     spawn_variant_panel(&mut commands, &font_handle);
+    spawn_recovery_dialog(&mut commands, &font_handle);
     spawn_audio_controls(&mut commands, &font_handle, &gear_icon, &audio_state);
 
     // Only show the FPS counter in debug mode
@@ -332,7 +601,7 @@ fn spawn_audio_controls(
                 .with_children(|expanded| {
                     expanded.spawn(control_text(
                         font_handle,
-                        "Audio Settings",
+                        "Settings",
                         CONTROL_TITLE_FONT_SIZE,
                         CONTROL_TEXT,
                     ));
@@ -447,11 +716,39 @@ fn spawn_audio_controls(
                             ),
                         ));
                     });
+
+                    expanded
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: px(8),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn(dialog_button(
+                                font_handle,
+                                "Copy Save Code",
+                                observe(copy_save_code),
+                            ));
+                            row.spawn(dialog_button(
+                                font_handle,
+                                "Paste Save Code",
+                                observe(paste_save_code),
+                            ));
+                        });
+                    expanded.spawn((
+                        SaveCodeStatus,
+                        control_text(
+                            font_handle,
+                            "",
+                            VARIANT_PANEL_STAT_FONT_SIZE,
+                            CONTROL_MUTED_TEXT,
+                        ),
+                    ));
                 });
         });
 }
 
-fn control_text(
+pub fn control_text(
     font_handle: &Handle<Font>,
     text: impl Into<String>,
     font_size: f32,
@@ -711,7 +1008,7 @@ fn update_score(
 
     for (mut text, name) in query.iter_mut() {
         match name.as_str() {
-            "score_text" => text.0 = format!("{:.2}", data.get_currency()),
+            "score_text" => text.0 = data.get_currency().to_string(),
             "fps_text" => {
                 if let Some(fps) = fps {
                     text.0 = format!("FPS: {:.0}", fps);
@@ -722,135 +1019,674 @@ fn update_score(
     }
 }
 
-fn prereq_not_met(variant: AutomatonVariant, game_data: &GameData) -> String {
-    match game_data.unmet_unlock_requirement(variant) {
-        Some(UnlockRequirement::PreviousAutomaton {
-            variant: required_variant,
-            quantity,
-        }) => format!(
-            "Requires {} {}",
-            quantity,
-            required_variant.label_for_quantity(quantity)
-        ),
-        Some(UnlockRequirement::FirstPurchaseCost) => {
-            format!(
-                "Requires {} Entropy",
-                game_data.get_cost_to_add_source(variant)
-            )
-        }
-        Some(UnlockRequirement::None) | None => "Locked".to_string(),
-    }
-}
-
-fn update_audio_panel_visibility(
+fn show_save_failures(
     time: Res<Time>,
-    mut queries: AudioPanelVisibilityQueries<'_, '_>,
+    mut failures: MessageReader<SaveFailed>,
+    mut remaining: Local<f32>,
+    mut notices: Query<(&mut Text, &mut Visibility), With<SaveNotice>>,
 ) {
-    let dt = time.delta_secs();
-    let cursor_in_window = queries
-        .windows
-        .iter()
-        .any(|w| w.cursor_position().is_some());
-    for (panel_entity, hovered, mut anim, mut panel_node) in &mut queries.panels {
-        let target = if cursor_in_window && hovered.get() {
-            1.0
-        } else {
-            0.0
-        };
-        let delta = (target - anim.progress) * AUDIO_PANEL_ANIM_SPEED * dt;
-        anim.progress = (anim.progress + delta).clamp(0.0, 1.0);
-        if (target - anim.progress).abs() < 0.001 {
-            anim.progress = target;
-        }
-        let t = ease_in_out(anim.progress);
-
-        let width = lerp(AUDIO_PANEL_COLLAPSED_SIZE, AUDIO_PANEL_EXPANDED_WIDTH, t);
-        let height = lerp(AUDIO_PANEL_COLLAPSED_SIZE, AUDIO_PANEL_EXPANDED_HEIGHT, t);
-        panel_node.width = px(width);
-        panel_node.height = px(height);
-
-        let mut expanded_entity: Option<Entity> = None;
-        for child in queries.children.iter_descendants(panel_entity) {
-            if let Ok((entity, mut node)) = queries.expanded.get_mut(child) {
-                node.display = if anim.progress > 0.001 {
-                    Display::Flex
-                } else {
-                    Display::None
-                };
-                expanded_entity = Some(entity);
-            }
-            if let Ok((mut node, btn_children)) = queries.collapsed.get_mut(child) {
-                node.display = if anim.progress < 0.999 {
-                    Display::Flex
-                } else {
-                    Display::None
-                };
-                let alpha = 1.0 - t;
-                if let Some(btn_children) = btn_children {
-                    for c in btn_children.iter() {
-                        if let Ok(mut color) = queries.text_colors.get_mut(c) {
-                            color.0 = CONTROL_TEXT.with_alpha(alpha);
-                        }
-                        if let Ok(mut image) = queries.gear_icons.get_mut(c) {
-                            image.color = CONTROL_TEXT.with_alpha(alpha);
-                        }
-                    }
-                }
-            }
+    if let Some(failure) = failures.read().last() {
+        *remaining = SAVE_NOTICE_DURATION;
+        for (mut text, _) in &mut notices {
+            text.0 = format!("Progress could not be saved: {}", failure.reason);
         }
+    } else if *remaining > 0.0 {
+        *remaining -= time.delta_secs();
+    }
 
-        if let Some(expanded_entity) = expanded_entity {
-            for descendant in queries.children.iter_descendants(expanded_entity) {
-                if let Ok(mut color) = queries.text_colors.get_mut(descendant) {
-                    let base = color.0;
-                    color.0 = base.with_alpha(t);
-                }
-            }
-        }
+    let visibility = if *remaining > 0.0 {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for (_, mut current) in &mut notices {
+        current.set_if_neq(visibility);
     }
 }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
-    a + (b - a) * t
+pub fn dialog_panel(width: f32) -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            top: percent(25),
+            left: percent(50),
+            margin: UiRect::left(px(-width / 2.0)),
+            width: px(width),
+            padding: UiRect::all(px(14)),
+            border: UiRect::all(px(1)),
+            border_radius: BorderRadius::all(px(6)),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(10),
+            ..default()
+        },
+        BackgroundColor(PANEL_BACKGROUND),
+        BorderColor::all(PANEL_BORDER),
+        GlobalZIndex(10),
+    )
 }
 
-fn ease_in_out(t: f32) -> f32 {
-    if t < 0.5 {
-        2.0 * t * t
-    } else {
-        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+pub fn dialog_button(font_handle: &Handle<Font>, label: &str, action: impl Bundle) -> impl Bundle {
+    (
+        DialogButton,
+        Node {
+            padding: UiRect::axes(px(10), px(8)),
+            border: UiRect::all(px(1)),
+            border_radius: BorderRadius::all(px(4)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(PANEL_BACKGROUND),
+        BorderColor::all(CONTROL_ACCENT),
+        Hovered::default(),
+        action,
+        children![(
+            TextLayout::new_with_justify(Justify::Center).with_no_wrap(),
+            control_text(
+                font_handle,
+                label,
+                VARIANT_PANEL_BUTTON_FONT_SIZE,
+                CONTROL_TEXT
+            ),
+            Pickable::IGNORE,
+        )],
+    )
+}
+
+fn update_dialog_button_style(mut buttons: DialogButtonStyleQuery<'_, '_>) {
+    for (hovered, mut bg, mut border) in &mut buttons {
+        bg.0 = if hovered.get() {
+            CONTROL_TRACK
+        } else {
+            PANEL_BACKGROUND
+        };
+        border.set_all(if hovered.get() {
+            CONTROL_ACCENT_HOVERED
+        } else {
+            CONTROL_ACCENT
+        });
     }
 }
 
-fn spawn_variant_panel(commands: &mut Commands, font_handle: &Handle<Font>) {
+fn spawn_recovery_dialog(commands: &mut Commands, font_handle: &Handle<Font>) {
     commands
-        .spawn((
-            VariantPanel,
-            Node {
-                position_type: PositionType::Absolute,
-                top: px(16),
-                left: percent(50),
-                margin: UiRect::left(px(-360)),
-                width: px(720),
-                padding: UiRect::all(px(14)),
-                border: UiRect::all(px(1)),
-                border_radius: BorderRadius::all(px(6)),
-                display: Display::None,
-                flex_direction: FlexDirection::Row,
-                align_items: AlignItems::Stretch,
-                column_gap: px(16),
-                ..default()
-            },
-            BackgroundColor(PANEL_BACKGROUND),
-            BorderColor::all(PANEL_BORDER),
-        ))
-        .with_children(|panel| {
-            // Left column: title + stats
-            panel
+        .spawn((RecoveryDialog, dialog_panel(460.0)))
+        .with_children(|dialog| {
+            dialog.spawn(control_text(
+                font_handle,
+                "Your save could not be loaded",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            dialog.spawn((
+                RecoveryDialogMessage,
+                control_text(font_handle, "", CONTROL_TEXT_FONT_SIZE, CONTROL_MUTED_TEXT),
+            ));
+            dialog
                 .spawn(Node {
-                    flex_grow: 1.0,
-                    flex_direction: FlexDirection::Column,
-                    row_gap: px(6),
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::FlexEnd,
+                    column_gap: px(8),
+                    ..default()
+                })
+                .with_children(|actions| {
+                    for (action, label) in [
+                        (RecoveryDialogButton::StartFresh, "Start Fresh"),
+                        (RecoveryDialogButton::LoadBackup, "Load Backup"),
+                        (RecoveryDialogButton::Quit, "Quit"),
+                    ] {
+                        actions.spawn(dialog_button(
+                            font_handle,
+                            label,
+                            (action, observe(on_recovery_dialog_button)),
+                        ));
+                    }
+                });
+        });
+}
+
+fn update_recovery_dialog(
+    recovery: Option<Res<SaveRecovery>>,
+    mut dialogs: Query<&mut Node, With<RecoveryDialog>>,
+    mut messages: Query<&mut Text, With<RecoveryDialogMessage>>,
+    mut buttons: Query<(&RecoveryDialogButton, &mut Node), Without<RecoveryDialog>>,
+) {
+    let display = if recovery.is_some() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    for mut node in &mut dialogs {
+        node.display = display;
+    }
+    let Some(recovery) = recovery.filter(|recovery| recovery.is_changed()) else {
+        return;
+    };
+
+    let backup_note = match &recovery.backup {
+        Some((generation, _)) => {
+            format!("Backup {generation} is intact and can be loaded instead.")
+        }
+        None => "No intact backup was found.".to_string(),
+    };
+    for mut text in &mut messages {
+        text.0 = format!(
+            "{}\nThe damaged save has been kept aside for debugging.\n{backup_note}",
+            recovery.error
+        );
+    }
+    for (action, mut node) in &mut buttons {
+        if *action == RecoveryDialogButton::LoadBackup {
+            node.display = if recovery.backup.is_some() {
+                Display::Flex
+            } else {
+                Display::None
+            };
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct RecoveryContext<'w> {
+    recovery: Option<ResMut<'w, SaveRecovery>>,
+    store: Res<'w, SaveStore>,
+    settings: Res<'w, OfflineProgressSettings>,
+    definitions: Res<'w, Definitions>,
+}
+
+fn on_recovery_dialog_button(
+    on: On<Pointer<Click>>,
+    actions: Query<&RecoveryDialogButton>,
+    mut commands: Commands,
+    context: RecoveryContext,
+    mut data: ResMut<GameData>,
+    mut exits: MessageWriter<AppExit>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    let RecoveryContext {
+        recovery,
+        store,
+        settings,
+        definitions,
+    } = context;
+    let (Ok(action), Some(mut recovery)) = (actions.get(on.event_target()), recovery) else {
+        return;
+    };
+    match action {
+        RecoveryDialogButton::StartFresh => {
+            keep_backups(&store);
+            *data = GameData::default();
+            commands.remove_resource::<SaveRecovery>();
+        }
+        RecoveryDialogButton::LoadBackup => {
+            if let Some((_, mut backup)) = recovery.backup.take() {
+                keep_backups(&store);
+                backup.set_definitions(definitions.clone());
+                if let Some(report) = backup.apply_offline_progress(storage::unix_time(), &settings)
+                {
+                    commands.insert_resource(report);
+                }
+                backup.request_save();
+                *data = backup;
+                commands.remove_resource::<SaveRecovery>();
+            }
+        }
+        RecoveryDialogButton::Quit => {
+            exits.write(AppExit::Success);
+        }
+    }
+}
+
+/// Copies the backups aside before leaving recovery, since the next save
+/// rotates the oldest ones out.
+fn keep_backups(store: &SaveStore) {
+    match store.keep_copy(storage::unix_time()) {
+        Ok(kept) => info!("Kept a copy of the save backups at {}", kept.name()),
+        Err(err) => warn!("Could not keep a copy of the save backups: {err}"),
+    }
+}
+
+fn show_offline_summary(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    report: Option<Res<OfflineReport>>,
+    settings: Res<OfflineProgressSettings>,
+    definitions: Res<Definitions>,
+    summaries: Query<Entity, With<OfflineSummary>>,
+) {
+    let Some(report) = report else {
+        for summary in &summaries {
+            commands.entity(summary).despawn();
+        }
+        return;
+    };
+    if !report.is_added() {
+        return;
+    }
+
+    let font_handle = asset_server.load(FONT_PATH);
+    let mut away = format!("Away for {}", format_duration(report.away_seconds));
+    if report.credited_seconds < report.away_seconds {
+        away += &format!(" (credited {})", format_duration(report.credited_seconds));
+    }
+    away += &format!(
+        ", earning at {:.0}% efficiency",
+        settings.efficiency * 100.0
+    );
+
+    commands
+        .spawn((OfflineSummary, dialog_panel(420.0)))
+        .with_children(|dialog| {
+            dialog.spawn(control_text(
+                &font_handle,
+                "While you were away...",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            dialog.spawn(control_text(
+                &font_handle,
+                away,
+                CONTROL_TEXT_FONT_SIZE,
+                CONTROL_MUTED_TEXT,
+            ));
+            for &(variant, earned) in &report.earnings {
+                dialog.spawn(control_text(
+                    &font_handle,
+                    format!(
+                        "{}: +{} Entropy",
+                        definitions.source(variant).plural_display_name,
+                        earned
+                    ),
+                    CONTROL_TEXT_FONT_SIZE,
+                    CONTROL_TEXT,
+                ));
+            }
+            dialog.spawn(control_text(
+                &font_handle,
+                format!("Total: +{} Entropy", report.total()),
+                CONTROL_TEXT_FONT_SIZE,
+                CONTROL_ACCENT_HOVERED,
+            ));
+            dialog.spawn(dialog_button(
+                &font_handle,
+                "Continue",
+                observe(dismiss_offline_summary),
+            ));
+        });
+}
+
+fn dismiss_offline_summary(on: On<Pointer<Click>>, mut commands: Commands) {
+    if on.button == PointerButton::Primary {
+        commands.remove_resource::<OfflineReport>();
+    }
+}
+
+fn copy_save_code(
+    on: On<Pointer<Click>>,
+    data: Res<GameData>,
+    mut statuses: Query<&mut Text, With<SaveCodeStatus>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    let status = match clipboard::copy(&save::export_code(&data)) {
+        Ok(()) => "Save code copied to the clipboard".to_string(),
+        Err(err) => format!("Could not copy: {err}"),
+    };
+    for mut text in &mut statuses {
+        text.0 = status.clone();
+    }
+}
+
+fn paste_save_code(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut statuses: Query<&mut Text, With<SaveCodeStatus>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    let status = match clipboard::paste() {
+        Ok(Some(code)) => match save::import_code(&code) {
+            Ok(incoming) => {
+                commands.insert_resource(ImportPreview { incoming });
+                String::new()
+            }
+            Err(err) => format!("Could not import: {err}"),
+        },
+        Ok(None) => String::new(),
+        Err(err) => format!("Could not paste: {err}"),
+    };
+    for mut text in &mut statuses {
+        text.0 = status.clone();
+    }
+}
+
+fn show_import_preview(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    preview: Option<Res<ImportPreview>>,
+    data: Res<GameData>,
+    dialogs: Query<Entity, With<ImportPreviewDialog>>,
+) {
+    let Some(preview) = preview else {
+        for dialog in &dialogs {
+            commands.entity(dialog).despawn();
+        }
+        return;
+    };
+    if !preview.is_changed() {
+        return;
+    }
+    for dialog in &dialogs {
+        commands.entity(dialog).despawn();
+    }
+
+    let font_handle = asset_server.load(FONT_PATH);
+    let summarise = |data: &GameData| {
+        let owned: u64 = data
+            .definitions()
+            .automatons()
+            .map(|definition| data.get_quantity_owned_by_source(definition.variant))
+            .sum();
+        [
+            data.get_currency().to_string(),
+            owned.to_string(),
+            data.total_generated().to_string(),
+            data.highest_unlocked()
+                .map_or("None", |variant| {
+                    data.definitions().source(variant).display_name.as_str()
+                })
+                .to_string(),
+        ]
+    };
+    let (current, incoming) = (summarise(&data), summarise(&preview.incoming));
+    let rows = [
+        "Entropy",
+        "Automatons",
+        "Total Generated",
+        "Furthest Unlock",
+    ];
+
+    commands
+        .spawn((ImportPreviewDialog, dialog_panel(420.0)))
+        .with_children(|dialog| {
+            dialog.spawn(control_text(
+                &font_handle,
+                "Replace your progress?",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            let columns = std::iter::once(("", "Current", "Imported")).chain(
+                rows.iter().zip(current.iter().zip(&incoming)).map(
+                    |(label, (current, incoming))| (*label, current.as_str(), incoming.as_str()),
+                ),
+            );
+            for (label, current, incoming) in columns {
+                dialog
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        for (text, color) in [
+                            (label, CONTROL_MUTED_TEXT),
+                            (current, CONTROL_TEXT),
+                            (incoming, CONTROL_ACCENT_HOVERED),
+                        ] {
+                            row.spawn((
+                                Node {
+                                    width: percent(33),
+                                    ..default()
+                                },
+                                control_text(&font_handle, text, CONTROL_TEXT_FONT_SIZE, color),
+                            ));
+                        }
+                    });
+            }
+            dialog
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::FlexEnd,
+                    column_gap: px(8),
+                    ..default()
+                })
+                .with_children(|actions| {
+                    actions.spawn(dialog_button(
+                        &font_handle,
+                        "Replace",
+                        observe(confirm_import),
+                    ));
+                    actions.spawn(dialog_button(
+                        &font_handle,
+                        "Cancel",
+                        observe(cancel_import),
+                    ));
+                });
+        });
+}
+
+fn confirm_import(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    preview: Option<ResMut<ImportPreview>>,
+    mut data: ResMut<GameData>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    if let Some(mut preview) = preview {
+        *data = std::mem::take(&mut preview.incoming);
+        data.request_save();
+        commands.remove_resource::<ImportPreview>();
+    }
+}
+
+fn cancel_import(on: On<Pointer<Click>>, mut commands: Commands) {
+    if on.button == PointerButton::Primary {
+        commands.remove_resource::<ImportPreview>();
+    }
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{seconds}s"),
+        (0, _) => format!("{minutes}m"),
+        _ => format!("{hours}h {minutes}m"),
+    }
+}
+
+fn prereq_not_met(variant: AutomatonVariant, game_data: &GameData) -> String {
+    match game_data.unmet_unlock_requirement(variant) {
+        Some(UnlockRequirement::None) | None => "Locked".to_string(),
+        Some(requirement) => format!(
+            "Requires {}",
+            requirement_progress(requirement, variant, game_data)
+        ),
+    }
+}
+
+/// Describes what is still missing from `requirement`, with progress where it
+/// can be counted, e.g. "12/20 Hellmites and 1/2 Abyssopod level".
+fn requirement_progress(
+    requirement: &UnlockRequirement,
+    source: AutomatonVariant,
+    game_data: &GameData,
+) -> String {
+    match requirement {
+        UnlockRequirement::None => "nothing".to_string(),
+        UnlockRequirement::FirstPurchaseCost => {
+            format!("{} Entropy", game_data.get_cost_to_add_source(source))
+        }
+        UnlockRequirement::PreviousAutomaton { variant, quantity } => format!(
+            "{}/{} {}",
+            game_data.get_quantity_owned_by_source(*variant),
+            quantity,
+            game_data
+                .definitions()
+                .source(*variant)
+                .label_for_quantity(*quantity)
+        ),
+        UnlockRequirement::LifetimeEntropy(amount) => format!(
+            "{}/{} lifetime Entropy",
+            game_data.total_generated(),
+            amount
+        ),
+        UnlockRequirement::VariantLevel { variant, level } => format!(
+            "{}/{} {} level",
+            game_data.get_level(*variant),
+            level,
+            game_data.definitions().source(*variant).display_name
+        ),
+        UnlockRequirement::PortalClicks(count) => {
+            format!("{}/{} portal clicks", game_data.get_portal_clicks(), count)
+        }
+        UnlockRequirement::WakeUps(count) => {
+            format!("{}/{} wake-ups", game_data.get_prestige_count(), count)
+        }
+        UnlockRequirement::All(requirements) => requirements
+            .iter()
+            .filter(|r| !r.is_met(source, game_data))
+            .map(|r| nested_requirement_progress(r, source, game_data))
+            .collect::<Vec<_>>()
+            .join(" and "),
+        UnlockRequirement::Any(requirements) => requirements
+            .iter()
+            .map(|r| nested_requirement_progress(r, source, game_data))
+            .collect::<Vec<_>>()
+            .join(" or "),
+    }
+}
+
+/// Like `requirement_progress`, bracketing groups so nesting stays readable.
+fn nested_requirement_progress(
+    requirement: &UnlockRequirement,
+    source: AutomatonVariant,
+    game_data: &GameData,
+) -> String {
+    let text = requirement_progress(requirement, source, game_data);
+    match requirement {
+        UnlockRequirement::All(requirements) | UnlockRequirement::Any(requirements)
+            if requirements.len() > 1 =>
+        {
+            format!("({text})")
+        }
+        _ => text,
+    }
+}
+
+fn update_audio_panel_visibility(
+    time: Res<Time>,
+    mut queries: AudioPanelVisibilityQueries<'_, '_>,
+) {
+    let dt = time.delta_secs();
+    let cursor_in_window = queries
+        .windows
+        .iter()
+        .any(|w| w.cursor_position().is_some());
+    for (panel_entity, hovered, mut anim, mut panel_node) in &mut queries.panels {
+        let target = if cursor_in_window && hovered.get() {
+            1.0
+        } else {
+            0.0
+        };
+        let delta = (target - anim.progress) * AUDIO_PANEL_ANIM_SPEED * dt;
+        anim.progress = (anim.progress + delta).clamp(0.0, 1.0);
+        if (target - anim.progress).abs() < 0.001 {
+            anim.progress = target;
+        }
+        let t = ease_in_out(anim.progress);
+
+        let width = lerp(AUDIO_PANEL_COLLAPSED_SIZE, AUDIO_PANEL_EXPANDED_WIDTH, t);
+        let height = lerp(AUDIO_PANEL_COLLAPSED_SIZE, AUDIO_PANEL_EXPANDED_HEIGHT, t);
+        panel_node.width = px(width);
+        panel_node.height = px(height);
+
+        let mut expanded_entity: Option<Entity> = None;
+        for child in queries.children.iter_descendants(panel_entity) {
+            if let Ok((entity, mut node)) = queries.expanded.get_mut(child) {
+                node.display = if anim.progress > 0.001 {
+                    Display::Flex
+                } else {
+                    Display::None
+                };
+                expanded_entity = Some(entity);
+            }
+            if let Ok((mut node, btn_children)) = queries.collapsed.get_mut(child) {
+                node.display = if anim.progress < 0.999 {
+                    Display::Flex
+                } else {
+                    Display::None
+                };
+                let alpha = 1.0 - t;
+                if let Some(btn_children) = btn_children {
+                    for c in btn_children.iter() {
+                        if let Ok(mut color) = queries.text_colors.get_mut(c) {
+                            color.0 = CONTROL_TEXT.with_alpha(alpha);
+                        }
+                        if let Ok(mut image) = queries.gear_icons.get_mut(c) {
+                            image.color = CONTROL_TEXT.with_alpha(alpha);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(expanded_entity) = expanded_entity {
+            for descendant in queries.children.iter_descendants(expanded_entity) {
+                if let Ok(mut color) = queries.text_colors.get_mut(descendant) {
+                    let base = color.0;
+                    color.0 = base.with_alpha(t);
+                }
+            }
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn ease_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+fn spawn_variant_panel(commands: &mut Commands, font_handle: &Handle<Font>) {
+    commands
+        .spawn((
+            VariantPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: px(16),
+                left: percent(50),
+                margin: UiRect::left(px(-360)),
+                width: px(720),
+                padding: UiRect::all(px(14)),
+                border: UiRect::all(px(1)),
+                border_radius: BorderRadius::all(px(6)),
+                display: Display::None,
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Stretch,
+                column_gap: px(16),
+                ..default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            BorderColor::all(PANEL_BORDER),
+        ))
+        .with_children(|panel| {
+            // Left column: title + stats
+            panel
+                .spawn(Node {
+                    flex_grow: 1.0,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: px(6),
                     ..default()
                 })
                 .with_children(|info| {
@@ -907,6 +1743,20 @@ fn spawn_variant_panel(commands: &mut Commands, font_handle: &Handle<Font>) {
                             });
                         }
                     });
+
+                    for stat in [VariantStat::Synergies, VariantStat::PortalDetails] {
+                        info.spawn((
+                            VariantPanelStat(stat),
+                            TextFont {
+                                font: font_handle.clone(),
+                                font_size: VARIANT_PANEL_STAT_FONT_SIZE,
+                                ..default()
+                            },
+                            Text::new(""),
+                            TextColor(CONTROL_MUTED_TEXT),
+                            Pickable::IGNORE,
+                        ));
+                    }
                 });
 
             // Right column: action buttons stacked vertically
@@ -921,40 +1771,143 @@ fn spawn_variant_panel(commands: &mut Commands, font_handle: &Handle<Font>) {
                     ..default()
                 })
                 .with_children(|actions| {
-                    for action in [VariantPanelButton::Summon, VariantPanelButton::LevelUp] {
-                        actions
-                            .spawn((
-                                action,
-                                Node {
-                                    width: percent(100),
-                                    padding: UiRect::axes(px(10), px(8)),
-                                    border: UiRect::all(px(1)),
-                                    border_radius: BorderRadius::all(px(4)),
-                                    display: Display::Flex,
-                                    justify_content: JustifyContent::Center,
-                                    align_items: AlignItems::Center,
-                                    overflow: Overflow::clip(),
-                                    ..default()
-                                },
-                                BackgroundColor(PANEL_BACKGROUND),
-                                BorderColor::all(PANEL_BORDER),
-                                Hovered::default(),
-                                observe(on_variant_panel_button),
-                            ))
-                            .with_children(|btn| {
-                                btn.spawn((
-                                    VariantPanelButtonLabel,
-                                    TextLayout::new_with_justify(Justify::Center).with_no_wrap(),
-                                    TextFont {
-                                        font: font_handle.clone(),
-                                        font_size: VARIANT_PANEL_BUTTON_FONT_SIZE,
+                    actions
+                        .spawn((
+                            BuyAmountToggle,
+                            Node {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: px(4),
+                                ..default()
+                            },
+                        ))
+                        .with_children(|toggle| {
+                            for amount in BuyAmount::ALL {
+                                toggle.spawn((
+                                    BuyAmountButton(amount),
+                                    Node {
+                                        flex_grow: 1.0,
+                                        padding: UiRect::axes(px(4), px(2)),
+                                        border: UiRect::all(px(1)),
+                                        border_radius: BorderRadius::all(px(4)),
+                                        justify_content: JustifyContent::Center,
                                         ..default()
                                     },
-                                    Text::new(""),
-                                    TextColor(CONTROL_TEXT),
-                                    Pickable::IGNORE,
+                                    BackgroundColor(PANEL_BACKGROUND),
+                                    BorderColor::all(PANEL_BORDER),
+                                    observe(on_buy_amount_button),
+                                    children![(
+                                        control_text(
+                                            font_handle,
+                                            amount.label(),
+                                            VARIANT_PANEL_BUTTON_FONT_SIZE,
+                                            CONTROL_TEXT,
+                                        ),
+                                        Pickable::IGNORE,
+                                    )],
                                 ));
-                            });
+                            }
+                        });
+
+                    actions
+                        .spawn((
+                            AutoBuyerControls,
+                            Node {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: px(4),
+                                ..default()
+                            },
+                        ))
+                        .with_children(|controls| {
+                            for button in [
+                                AutoBuyerButton::Toggle,
+                                AutoBuyerButton::Interval,
+                                AutoBuyerButton::Policy,
+                            ] {
+                                controls.spawn((
+                                    button,
+                                    Node {
+                                        flex_grow: 1.0,
+                                        padding: UiRect::axes(px(4), px(2)),
+                                        border: UiRect::all(px(1)),
+                                        border_radius: BorderRadius::all(px(4)),
+                                        justify_content: JustifyContent::Center,
+                                        ..default()
+                                    },
+                                    BackgroundColor(PANEL_BACKGROUND),
+                                    BorderColor::all(PANEL_BORDER),
+                                    observe(on_auto_buyer_button),
+                                    children![(
+                                        AutoBuyerLabel(button),
+                                        control_text(
+                                            font_handle,
+                                            "",
+                                            VARIANT_PANEL_BUTTON_FONT_SIZE,
+                                            CONTROL_TEXT,
+                                        ),
+                                        Pickable::IGNORE,
+                                    )],
+                                ));
+                            }
+                        });
+
+                    for action in [
+                        VariantPanelButton::Summon,
+                        VariantPanelButton::LevelUp,
+                        VariantPanelButton::Banish,
+                    ] {
+                        let mut button = actions.spawn((
+                            action,
+                            Node {
+                                width: percent(100),
+                                padding: UiRect::axes(px(10), px(8)),
+                                border: UiRect::all(px(1)),
+                                border_radius: BorderRadius::all(px(4)),
+                                display: Display::Flex,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                overflow: Overflow::clip(),
+                                ..default()
+                            },
+                            BackgroundColor(PANEL_BACKGROUND),
+                            BorderColor::all(PANEL_BORDER),
+                            Hovered::default(),
+                            observe(on_variant_panel_button),
+                        ));
+                        if matches!(action, VariantPanelButton::Banish) {
+                            button.insert((
+                                HoldToConfirm::default(),
+                                observe(start_hold),
+                                observe(cancel_hold::<Release>),
+                                observe(cancel_hold::<Out>),
+                            ));
+                            button.with_child((
+                                HoldProgressFill,
+                                Node {
+                                    position_type: PositionType::Absolute,
+                                    left: px(0),
+                                    top: px(0),
+                                    bottom: px(0),
+                                    width: percent(0),
+                                    ..default()
+                                },
+                                BackgroundColor(WARNING_TEXT.with_alpha(0.35)),
+                                Pickable::IGNORE,
+                            ));
+                        }
+                        button.with_children(|btn| {
+                            btn.spawn((
+                                VariantPanelButtonLabel,
+                                TextLayout::new_with_justify(Justify::Center).with_no_wrap(),
+                                TextFont {
+                                    font: font_handle.clone(),
+                                    font_size: VARIANT_PANEL_BUTTON_FONT_SIZE,
+                                    ..default()
+                                },
+                                Text::new(""),
+                                TextColor(CONTROL_TEXT),
+                                Pickable::IGNORE,
+                            ));
+                        });
                     }
                 });
         });
@@ -963,48 +1916,26 @@ fn spawn_variant_panel(commands: &mut Commands, font_handle: &Handle<Font>) {
 fn update_variant_panel(
     interface_data: Res<InterfaceState>,
     data: Res<GameData>,
-    mut panels: Query<&mut Node, With<VariantPanel>>,
-    mut titles: Query<&mut Text, With<VariantPanelTitle>>,
-    mut stats: Query<
-        (&VariantPanelStat, &mut Text),
-        (Without<VariantPanelTitle>, Without<VariantPanelButtonLabel>),
-    >,
-    mut buttons: Query<
-        (
-            &VariantPanelButton,
-            &Hovered,
-            &mut Node,
-            &mut BackgroundColor,
-            &mut BorderColor,
-            &Children,
-        ),
-        Without<VariantPanel>,
-    >,
-    mut button_labels: Query<
-        &mut Text,
-        (
-            With<VariantPanelButtonLabel>,
-            Without<VariantPanelTitle>,
-            Without<VariantPanelStat>,
-        ),
-    >,
-    mut label_colors: Query<&mut TextColor, With<VariantPanelButtonLabel>>,
+    mut queries: VariantPanelQueries<'_, '_>,
 ) {
     let Some(source) = interface_data.selected_automaton else {
-        for mut node in &mut panels {
+        for mut node in &mut queries.panels {
             node.display = Display::None;
         }
         return;
     };
 
-    for mut node in &mut panels {
+    for mut node in &mut queries.panels {
         node.display = Display::Flex;
     }
 
     let quantity = data.get_quantity_owned_by_source(source);
-    for mut text in &mut titles {
+    for mut text in &mut queries.titles {
         text.0 = if source.is_automaton() {
-            source.label_for_quantity(quantity).to_string()
+            data.definitions()
+                .source(source)
+                .label_for_quantity(quantity)
+                .to_string()
         } else {
             "The Portal".to_string()
         };
@@ -1015,18 +1946,21 @@ fn update_variant_panel(
     let rate = data.rate_per_second_by_source(source);
     let generated = data.get_currency_by_source(source);
     let prereq_met = source.is_automaton() && data.prerequisites_met(source);
-    let summon_cost = data.get_cost_to_add_source(source);
-    let summon_affordable = source.is_automaton() && prereq_met && data.can_afford_source(source);
+    let summon_count = interface_data.buy_amount.count(&data, source);
+    let summon_cost = data.get_cost_to_add_sources(source, summon_count);
+    let summon_affordable =
+        source.is_automaton() && prereq_met && data.get_currency() >= summon_cost;
     let level_up_cost = data.cost_to_level_up(source);
     let level_up_affordable = data.can_level_up(source);
+    let banish_count = interface_data.buy_amount.banish_count(&data, source);
 
-    for (kind, mut text) in &mut stats {
+    for (kind, mut text) in &mut queries.stats {
         text.0 = match kind.0 {
             VariantStat::Owned => {
                 if source.is_automaton() {
                     format!("Owned: {}", quantity)
                 } else {
-                    "".into()
+                    format!("Clicks: {}", data.get_portal_clicks())
                 }
             }
             VariantStat::Generated => format!("Generated: {}", generated),
@@ -1034,107 +1968,147 @@ fn update_variant_panel(
                 if source.is_automaton() {
                     format!("Rate: {:.2}/s", rate)
                 } else {
-                    "".into()
+                    format!("Click: {}", data.portal_click_value())
                 }
             }
             VariantStat::Level => {
-                if source.is_automaton() {
-                    format!("Level {} (x{:.2})", level, multiplier)
+                if !source.is_automaton() {
+                    format!("Crits: {}", data.get_portal_crits())
+                } else if let Some(max_level) = data.max_level(source) {
+                    format!("Level {}/{} (x{:.2})", level, max_level, multiplier)
                 } else {
+                    format!("Level {} (x{:.2})", level, multiplier)
+                }
+            }
+            VariantStat::Synergies => active_synergies(&data, source)
+                .map(|(synergy, steps)| synergy.describe(steps, &data))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            VariantStat::PortalDetails => {
+                if source.is_automaton() {
                     "".into()
+                } else {
+                    portal_details(&data)
                 }
             }
         };
     }
 
-    for (action, hovered, mut node, mut bg, mut border, children) in &mut buttons {
-        match action {
+    for (action, hovered, mut node, mut bg, mut border, children) in &mut queries.buttons {
+        if !source.is_automaton() {
+            node.display = Display::None;
+            continue;
+        }
+        node.display = Display::Flex;
+        let (label, enabled) = match action {
             VariantPanelButton::Summon => {
-                if !source.is_automaton() {
-                    node.display = Display::None;
-                    continue;
-                }
-                node.display = Display::Flex;
                 let label = if !prereq_met {
                     prereq_not_met(source, &data)
                 } else {
-                    format!("Summon ({} Entropy)", summon_cost)
-                };
-                set_button_label(children, &mut button_labels, &label);
-
-                let active = summon_affordable && hovered.get();
-                bg.0 = if active {
-                    CONTROL_TRACK
-                } else {
-                    PANEL_BACKGROUND
+                    format!("Summon {summon_count} ({summon_cost} Entropy)")
                 };
-                border.set_all(if active {
-                    CONTROL_ACCENT_HOVERED
-                } else if summon_affordable {
-                    CONTROL_ACCENT
-                } else {
-                    PANEL_BORDER
-                });
-                for child in children.iter() {
-                    if let Ok(mut color) = label_colors.get_mut(child) {
-                        color.0 = if summon_affordable {
-                            CONTROL_TEXT
-                        } else {
-                            CONTROL_MUTED_TEXT
-                        };
-                    }
-                }
+                (label, summon_affordable)
             }
             VariantPanelButton::LevelUp => {
-                if !source.is_automaton() {
-                    node.display = Display::None;
-                    continue;
-                }
-                node.display = Display::Flex;
-                let label = format!(
-                    "Level Up ({} {})",
-                    level_up_cost,
-                    source.label_for_quantity(level_up_cost)
-                );
-                set_button_label(children, &mut button_labels, &label);
-
-                let active = level_up_affordable && hovered.get();
-                bg.0 = if active {
-                    CONTROL_TRACK
+                let label = match level_up_cost {
+                    Some(cost) => level_up_label(&data, source, level, cost),
+                    None => "Max Level".to_string(),
+                };
+                (label, level_up_affordable)
+            }
+            VariantPanelButton::Banish => {
+                let label = if banish_count == 0 {
+                    "Nothing to Banish".to_string()
                 } else {
-                    PANEL_BACKGROUND
+                    format!(
+                        "Hold to Banish {banish_count} (+{} Entropy)",
+                        data.refund_for_sources(source, banish_count)
+                    )
                 };
-                border.set_all(if active {
-                    CONTROL_ACCENT_HOVERED
-                } else if level_up_affordable {
-                    CONTROL_ACCENT
+                (label, banish_count > 0)
+            }
+        };
+        set_button_label(children, &mut queries.button_labels, &label);
+
+        let active = enabled && hovered.get();
+        bg.0 = if active {
+            CONTROL_TRACK
+        } else {
+            PANEL_BACKGROUND
+        };
+        border.set_all(if active {
+            CONTROL_ACCENT_HOVERED
+        } else if enabled {
+            CONTROL_ACCENT
+        } else {
+            PANEL_BORDER
+        });
+        for child in children.iter() {
+            if let Ok(mut color) = queries.label_colors.get_mut(child) {
+                color.0 = if enabled {
+                    CONTROL_TEXT
                 } else {
-                    PANEL_BORDER
-                });
-                for child in children.iter() {
-                    if let Ok(mut color) = label_colors.get_mut(child) {
-                        color.0 = if level_up_affordable {
-                            CONTROL_TEXT
-                        } else {
-                            CONTROL_MUTED_TEXT
-                        };
-                    }
-                }
+                    CONTROL_MUTED_TEXT
+                };
             }
         }
     }
 }
 
+fn portal_details(data: &GameData) -> String {
+    let upgrades: Vec<&str> = data
+        .definitions()
+        .upgrades
+        .iter()
+        .filter(|upgrade| data.has_upgrade(&upgrade.id))
+        .filter(|upgrade| {
+            matches!(
+                upgrade.effect,
+                UpgradeEffect::PortalClickPower { .. }
+                    | UpgradeEffect::PortalRateShare { .. }
+                    | UpgradeEffect::PortalCritChance { .. }
+            )
+        })
+        .map(|upgrade| upgrade.name.as_str())
+        .collect();
+    format!(
+        "Best click: {}\nCrit chance: {:.0}% (x{})\nUpgrades: {}",
+        data.get_best_click(),
+        data.portal_crit_chance() * 100.0,
+        CRIT_MULTIPLIER,
+        if upgrades.is_empty() {
+            "none".to_string()
+        } else {
+            upgrades.join(", ")
+        }
+    )
+}
+
+/// Shows the level-up cost, the multiplier before and after, and any
+/// milestone reached, e.g. "Level Up (75 Hellmites): x1.25 -> x1.56".
+fn level_up_label(data: &GameData, source: AutomatonVariant, level: u32, cost: u64) -> String {
+    let definition = data.definitions().source(source);
+    let mut label = format!(
+        "Level Up ({} {}): x{:.2} -> x{:.2}",
+        cost,
+        definition.label_for_quantity(cost),
+        data.level_multiplier_at(source, level),
+        data.level_multiplier_at(source, level + 1)
+    );
+    let milestones = definition
+        .leveling
+        .iter()
+        .flat_map(|leveling| &leveling.milestones)
+        .filter(|milestone| milestone.level == level + 1);
+    for milestone in milestones {
+        label += &format!(", {}", milestone.bonus.describe());
+    }
+    label
+}
+
 fn set_button_label(
     children: &Children,
-    labels: &mut Query<
-        &mut Text,
-        (
-            With<VariantPanelButtonLabel>,
-            Without<VariantPanelTitle>,
-            Without<VariantPanelStat>,
-        ),
-    >,
+    labels: &mut VariantPanelButtonLabelQuery<'_, '_>,
     new_label: &str,
 ) {
     for child in children.iter() {
@@ -1161,10 +2135,235 @@ fn on_variant_panel_button(
     };
     match action {
         VariantPanelButton::Summon => {
-            data.purchase_source(source);
+            let count = interface_data.buy_amount.count(&data, source);
+            data.purchase_sources(source, count);
         }
         VariantPanelButton::LevelUp => {
             data.level_up(source);
         }
+        // Banishing happens in `update_banish_hold` once the press is held long enough.
+        VariantPanelButton::Banish => {}
+    }
+}
+
+fn start_hold(on: On<Pointer<Press>>, mut holds: Query<&mut HoldToConfirm>) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    if let Ok(mut hold) = holds.get_mut(on.event_target()) {
+        hold.held = true;
+    }
+}
+
+fn cancel_hold<E: Clone + Reflect + std::fmt::Debug>(
+    on: On<Pointer<E>>,
+    mut holds: Query<&mut HoldToConfirm>,
+) {
+    if let Ok(mut hold) = holds.get_mut(on.event_target()) {
+        hold.held = false;
+        hold.progress = 0.0;
+    }
+}
+
+fn update_banish_hold(
+    time: Res<Time>,
+    interface_data: Res<InterfaceState>,
+    mut data: ResMut<GameData>,
+    mut holds: Query<(&mut HoldToConfirm, &Children)>,
+    mut fills: Query<&mut Node, With<HoldProgressFill>>,
+    mut held_for: Local<Option<AutomatonVariant>>,
+) {
+    // A hold only counts for the automaton that was selected when it began.
+    let selection_changed = *held_for != interface_data.selected_automaton;
+    *held_for = interface_data.selected_automaton;
+    for (mut hold, children) in &mut holds {
+        if selection_changed {
+            *hold = HoldToConfirm::default();
+        }
+        if hold.held {
+            hold.progress += time.delta_secs() / BANISH_HOLD_SECONDS;
+            if hold.progress >= 1.0 {
+                // Require a fresh press before banishing again.
+                *hold = HoldToConfirm::default();
+                if let Some(source) = interface_data.selected_automaton {
+                    let count = interface_data.buy_amount.banish_count(&data, source);
+                    data.sell_source(source, count);
+                }
+            }
+        }
+        for child in children.iter() {
+            if let Ok(mut node) = fills.get_mut(child) {
+                node.width = percent(hold.progress * 100.0);
+            }
+        }
+    }
+}
+
+fn update_buy_amount_toggle(
+    interface_data: Res<InterfaceState>,
+    mut toggles: Query<&mut Node, With<BuyAmountToggle>>,
+    mut buttons: Query<(&BuyAmountButton, &mut BackgroundColor, &mut BorderColor)>,
+) {
+    if !interface_data.is_changed() {
+        return;
+    }
+    let shown = interface_data
+        .selected_automaton
+        .is_some_and(AutomatonVariant::is_automaton);
+    for mut node in &mut toggles {
+        node.display = if shown { Display::Flex } else { Display::None };
+    }
+    for (button, mut bg, mut border) in &mut buttons {
+        let selected = button.0 == interface_data.buy_amount;
+        bg.0 = if selected {
+            CONTROL_TRACK
+        } else {
+            PANEL_BACKGROUND
+        };
+        border.set_all(if selected {
+            CONTROL_ACCENT_HOVERED
+        } else {
+            PANEL_BORDER
+        });
+    }
+}
+
+fn on_buy_amount_button(
+    on: On<Pointer<Click>>,
+    buttons: Query<&BuyAmountButton>,
+    mut interface_data: ResMut<InterfaceState>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    if let Ok(button) = buttons.get(on.event_target()) {
+        interface_data.buy_amount = button.0;
+    }
+}
+
+type AutoBuyerButtonQuery<'w, 's> =
+    Query<'w, 's, (&'static AutoBuyerButton, &'static mut BorderColor), Without<AutoBuyerControls>>;
+
+fn update_auto_buyer_controls(
+    interface_data: Res<InterfaceState>,
+    data: Res<GameData>,
+    mut controls: Query<&mut Node, With<AutoBuyerControls>>,
+    mut buttons: AutoBuyerButtonQuery<'_, '_>,
+    mut labels: Query<(&AutoBuyerLabel, &mut Text)>,
+) {
+    if !interface_data.is_changed() && !data.is_changed() {
+        return;
+    }
+    let source = interface_data
+        .selected_automaton
+        .filter(|&source| data.auto_buyer_unlocked(source));
+    for mut node in &mut controls {
+        node.display = if source.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    let Some(source) = source else {
+        return;
+    };
+
+    let auto_buyer = data.auto_buyer(source);
+    for (button, mut border) in &mut buttons {
+        let highlighted = *button == AutoBuyerButton::Toggle && auto_buyer.enabled;
+        border.set_all(if highlighted {
+            CONTROL_ACCENT_HOVERED
+        } else {
+            PANEL_BORDER
+        });
+    }
+    for (label, mut text) in &mut labels {
+        text.0 = match label.0 {
+            AutoBuyerButton::Toggle => {
+                format!("Auto: {}", if auto_buyer.enabled { "On" } else { "Off" })
+            }
+            AutoBuyerButton::Interval => format!("Every {}s", auto_buyer.interval),
+            AutoBuyerButton::Policy => auto_buy_policy_label(auto_buyer.policy),
+        };
+    }
+}
+
+fn auto_buy_policy_label(policy: AutoBuyPolicy) -> String {
+    match policy {
+        AutoBuyPolicy::Always => "Always".to_string(),
+        AutoBuyPolicy::KeepReserve(reserve) => format!("Keep {reserve}"),
+        AutoBuyPolicy::MaxPayback(seconds) => format!("Payback < {seconds}s"),
+    }
+}
+
+/// The preset after `current`, or the first one if `current` is not a preset.
+fn next_preset<T: Copy + PartialEq>(presets: &[T], current: T) -> T {
+    let next = presets
+        .iter()
+        .position(|&preset| preset == current)
+        .map_or(0, |index| (index + 1) % presets.len());
+    presets[next]
+}
+
+fn on_auto_buyer_button(
+    on: On<Pointer<Click>>,
+    buttons: Query<&AutoBuyerButton>,
+    interface_data: Res<InterfaceState>,
+    mut data: ResMut<GameData>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    let (Ok(button), Some(source)) = (
+        buttons.get(on.event_target()),
+        interface_data.selected_automaton,
+    ) else {
+        return;
+    };
+    let mut auto_buyer = data.auto_buyer(source);
+    match button {
+        AutoBuyerButton::Toggle => auto_buyer.enabled = !auto_buyer.enabled,
+        AutoBuyerButton::Interval => {
+            auto_buyer.interval = next_preset(&AUTO_BUY_INTERVALS, auto_buyer.interval)
+        }
+        AutoBuyerButton::Policy => {
+            auto_buyer.policy = next_preset(&AUTO_BUY_POLICIES, auto_buyer.policy)
+        }
+    }
+    data.set_auto_buyer(source, auto_buyer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmet_requirements_read_as_progress() {
+        let mut game_data = GameData::default();
+        game_data.add_income(AutomatonVariant::Portal, Currency::new(10_000));
+        assert!(game_data.purchase_sources(AutomatonVariant::Hellmite, 12));
+        let requirement = UnlockRequirement::All(vec![
+            UnlockRequirement::PreviousAutomaton {
+                variant: AutomatonVariant::Hellmite,
+                quantity: 20,
+            },
+            UnlockRequirement::VariantLevel {
+                variant: AutomatonVariant::Abyssopod,
+                level: 2,
+            },
+            UnlockRequirement::LifetimeEntropy(Currency::new(5_000)),
+            UnlockRequirement::Any(vec![
+                UnlockRequirement::PortalClicks(50),
+                UnlockRequirement::VariantLevel {
+                    variant: AutomatonVariant::Hellmite,
+                    level: 1,
+                },
+            ]),
+        ]);
+
+        assert_eq!(
+            requirement_progress(&requirement, AutomatonVariant::GapingDubine, &game_data),
+            "12/20 Hellmites and 0/2 Abyssopod level and (0/50 portal clicks or 0/1 Hellmite level)"
+        );
     }
 }
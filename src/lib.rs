@@ -0,0 +1,37 @@
+//! The game's plugins and its economy. The economy in `data` runs without
+//! Bevy rendering, so tools such as the simulator can link it headless.
+
+use bevy::prelude::*;
+
+pub mod achievements;
+pub mod audio;
+pub mod automatons;
+pub mod buffs;
+pub mod camera;
+pub mod clipboard;
+pub mod currency;
+pub mod data;
+pub mod definitions;
+pub mod economy;
+pub mod environment;
+pub mod interface;
+pub mod portal;
+pub mod prestige;
+pub mod production;
+pub mod profiles;
+pub mod rand;
+pub mod rifts;
+pub mod save;
+pub mod simulation;
+pub mod storage;
+pub mod synergies;
+pub mod upgrades;
+
+/// The game waits in `ProfileSelect` until a profile's `GameData` is loaded;
+/// anything that reads `GameData` runs only in `Playing`.
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    ProfileSelect,
+    Playing,
+}
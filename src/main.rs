@@ -1,13 +1,8 @@
 use bevy::{input_focus::InputFocus, picking::mesh_picking::MeshPickingPlugin, prelude::*};
-
-mod audio;
-mod automatons;
-mod camera;
-mod data;
-mod environment;
-mod interface;
-mod portal;
-mod rand;
+use fever_dream_inc::{
+    AppState, achievements, audio, automatons, buffs, camera, definitions, economy, environment,
+    interface, portal, prestige, profiles, rand, rifts, save, upgrades,
+};
 
 fn main() {
     let mut app = App::new();
@@ -22,8 +17,12 @@ fn main() {
     }));
 
     app.init_resource::<InputFocus>();
+    app.init_resource::<rand::RandomSource>();
+    app.init_state::<AppState>();
 
-    app.insert_resource(data::GameData::restore());
+    app.add_plugins(definitions::DefinitionsPlugin);
+    app.add_plugins(save::SavePlugin);
+    app.add_plugins(profiles::ProfilesPlugin);
 
     app.add_plugins(MeshPickingPlugin);
     app.insert_resource(MeshPickingSettings {
@@ -38,6 +37,12 @@ fn main() {
     app.add_plugins(interface::InterfacePlugin);
 
     app.add_plugins(portal::PortalPlugin);
+    app.add_plugins(rifts::RiftsPlugin);
+    app.add_plugins(buffs::BuffsPlugin);
+    app.add_plugins(prestige::PrestigePlugin);
+    app.add_plugins(upgrades::UpgradesPlugin);
+    app.add_plugins(achievements::AchievementsPlugin);
+    app.add_plugins(economy::EconomyPlugin::default());
     app.add_plugins(automatons::AutomatonsPlugin);
 
     app.run();
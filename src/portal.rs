@@ -4,7 +4,10 @@ use bevy::{
 };
 use bevy_kira_audio::prelude::*;
 
-use crate::{audio, data::AutomatonVariant, interface::set_hovered_automaton, rand};
+use crate::{
+    AppState, audio, data::AutomatonVariant, economy::PortalClicked,
+    interface::set_hovered_automaton, rand::RandomSource,
+};
 
 pub struct PortalPlugin;
 
@@ -23,6 +26,7 @@ impl Plugin for PortalPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup);
         app.add_systems(Update, update);
+        app.add_systems(Update, launch_rings.run_if(in_state(AppState::Playing)));
     }
 }
 
@@ -113,25 +117,38 @@ fn unhover_portal(_on: On<Pointer<Out>>, mut query: Query<&mut PortalState>) {
 fn click_on_portal(
     on: On<Pointer<Click>>,
     mut game_data: ResMut<crate::data::GameData>,
-    mut rings: Query<(&mut Transform, &mut PortalRing)>,
-    audio_state: Res<crate::audio::AudioState>,
-    interaction: Res<AudioChannel<crate::audio::InteractionChannel>>,
+    mut random: ResMut<RandomSource>,
 ) {
     if on.button != PointerButton::Primary {
         return;
     }
+    game_data.click_portal(random.gameplay().random_range(0.0..1.0));
+}
 
-    audio::play_pickup_sound(&interaction, &audio_state);
+/// Plays the pickup sound and sends out a ring for every portal click.
+fn launch_rings(
+    mut clicks: MessageReader<PortalClicked>,
+    mut rings: Query<(&mut Transform, &mut PortalRing)>,
+    audio_state: Res<crate::audio::AudioState>,
+    interaction: Res<AudioChannel<crate::audio::InteractionChannel>>,
+    mut random: ResMut<RandomSource>,
+) {
+    let rng = random.visuals();
+    for click in clicks.read() {
+        audio::play_pickup_sound(&interaction, &audio_state);
 
-    game_data.add_income(AutomatonVariant::Portal, 1);
-    for (mut ring_transform, mut ring_state) in rings.iter_mut() {
-        if ring_transform.scale.x < 0.75 {
-            ring_transform.scale = Vec3::splat(1.0);
-            ring_state.grow = true;
-            ring_transform.rotate_x(rand::random_range(0.0..1.0));
-            ring_transform.rotate_y(rand::random_range(0.0..1.0));
-            ring_transform.rotate_z(rand::random_range(0.0..1.0));
-            break;
+        // A critical click sends out every idle ring at once.
+        for (mut ring_transform, mut ring_state) in rings.iter_mut() {
+            if ring_transform.scale.x < 0.75 {
+                ring_transform.scale = Vec3::splat(1.0);
+                ring_state.grow = true;
+                ring_transform.rotate_x(rng.random_range(0.0..1.0));
+                ring_transform.rotate_y(rng.random_range(0.0..1.0));
+                ring_transform.rotate_z(rng.random_range(0.0..1.0));
+                if !click.crit {
+                    break;
+                }
+            }
         }
     }
 }
@@ -0,0 +1,196 @@
+use bevy::{prelude::*, ui_widgets::observe};
+
+use crate::{
+    AppState,
+    data::GameData,
+    interface::{
+        CONTROL_MUTED_TEXT, CONTROL_TEXT, CONTROL_TEXT_FONT_SIZE, CONTROL_TITLE_FONT_SIZE,
+        FONT_PATH, control_text, dialog_button, dialog_panel,
+    },
+};
+
+/// Waking up (prestige): the HUD entry point and the confirmation dialog. The
+/// rules live on `GameData`.
+pub struct PrestigePlugin;
+
+impl Plugin for PrestigePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_prestige_controls);
+        app.add_systems(
+            Update,
+            update_prestige_controls.run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PrestigeControls;
+
+#[derive(Component)]
+struct PrestigeStatus;
+
+#[derive(Component)]
+struct PrestigeOpenButton;
+
+#[derive(Component)]
+struct PrestigeDialog;
+
+#[derive(Component)]
+struct PrestigePreview;
+
+fn spawn_prestige_controls(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_handle = asset_server.load(FONT_PATH);
+
+    commands
+        .spawn((
+            PrestigeControls,
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(8),
+                top: px(52),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexStart,
+                row_gap: px(6),
+                display: Display::None,
+                ..default()
+            },
+        ))
+        .with_children(|controls| {
+            controls.spawn((
+                PrestigeStatus,
+                control_text(&font_handle, "", CONTROL_TEXT_FONT_SIZE, CONTROL_MUTED_TEXT),
+                Pickable::IGNORE,
+            ));
+            controls.spawn(dialog_button(
+                &font_handle,
+                "Wake Up...",
+                (PrestigeOpenButton, observe(open_prestige_dialog)),
+            ));
+        });
+}
+
+fn update_prestige_controls(
+    data: Res<GameData>,
+    mut controls: Query<&mut Node, With<PrestigeControls>>,
+    mut open_buttons: Query<&mut Node, (With<PrestigeOpenButton>, Without<PrestigeControls>)>,
+    mut statuses: Query<&mut Text, (With<PrestigeStatus>, Without<PrestigePreview>)>,
+    mut previews: Query<&mut Text, (With<PrestigePreview>, Without<PrestigeStatus>)>,
+) {
+    if !data.is_changed() {
+        return;
+    }
+    let pending = data.pending_lucidity();
+    let shown = data.can_prestige() || data.get_prestige_count() > 0;
+    for mut node in &mut controls {
+        node.display = if shown { Display::Flex } else { Display::None };
+    }
+    for mut node in &mut open_buttons {
+        node.display = if data.can_prestige() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+
+    let mut status = format!(
+        "Lucidity: {} (x{:.2} production)",
+        data.get_lucidity(),
+        data.global_multiplier()
+    );
+    if !pending.is_zero() {
+        status += &format!(", +{pending} on waking");
+    }
+    for mut text in &mut statuses {
+        text.0 = status.clone();
+    }
+
+    let preview = format!(
+        "Waking up resets your Entropy, automatons, levels and upgrades.\n\
+         Lucidity: {} -> {}\n\
+         Production: x{:.2} -> x{:.2}",
+        data.get_lucidity(),
+        data.get_lucidity() + pending,
+        data.global_multiplier(),
+        data.global_multiplier_after_prestige()
+    );
+    for mut text in &mut previews {
+        text.0 = preview.clone();
+    }
+}
+
+fn open_prestige_dialog(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut data: ResMut<GameData>,
+    dialogs: Query<(), With<PrestigeDialog>>,
+) {
+    if on.button != PointerButton::Primary || !dialogs.is_empty() {
+        return;
+    }
+    // Fill in the preview on the next update.
+    data.set_changed();
+
+    let font_handle = asset_server.load(FONT_PATH);
+    commands
+        .spawn((PrestigeDialog, dialog_panel(420.0)))
+        .with_children(|dialog| {
+            dialog.spawn(control_text(
+                &font_handle,
+                "Wake up from the dream?",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            dialog.spawn((
+                PrestigePreview,
+                control_text(&font_handle, "", CONTROL_TEXT_FONT_SIZE, CONTROL_MUTED_TEXT),
+            ));
+            dialog
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::FlexEnd,
+                    column_gap: px(8),
+                    ..default()
+                })
+                .with_children(|actions| {
+                    actions.spawn(dialog_button(
+                        &font_handle,
+                        "Wake Up",
+                        observe(confirm_prestige),
+                    ));
+                    actions.spawn(dialog_button(
+                        &font_handle,
+                        "Keep Dreaming",
+                        observe(close_prestige_dialog),
+                    ));
+                });
+        });
+}
+
+fn confirm_prestige(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut data: ResMut<GameData>,
+    dialogs: Query<Entity, With<PrestigeDialog>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    data.prestige();
+    for dialog in &dialogs {
+        commands.entity(dialog).despawn();
+    }
+}
+
+fn close_prestige_dialog(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    dialogs: Query<Entity, With<PrestigeDialog>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    for dialog in &dialogs {
+        commands.entity(dialog).despawn();
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::data::{AutomatonVariant, GameData};
+
+/// Slack when counting whole ticks, so timesteps that add up to a whole tick
+/// count as one despite rounding.
+const TICK_EPSILON: f64 = 1e-9;
+
+/// Automaton ticks owed per variant that have not added up to a whole one
+/// yet. Each owned automaton ticks once per cooldown, so a variant earns
+/// `owned × currency_per_tick / cooldown` per second.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ProductionTicks(HashMap<AutomatonVariant, f64>);
+
+impl ProductionTicks {
+    /// Advances every variant by `seconds`, crediting the whole ticks that
+    /// completed as one `add_income` per variant.
+    pub fn advance(&mut self, data: &mut GameData, seconds: f64) {
+        let definitions = data.definitions().clone();
+        for definition in definitions.automatons() {
+            let variant = definition.variant;
+            let owned = data.get_quantity_owned_by_source(variant);
+            if owned == 0 {
+                self.0.remove(&variant);
+                continue;
+            }
+            let ticks = self.0.entry(variant).or_default();
+            *ticks += owned as f64 * seconds / data.cooldown(variant) as f64;
+            let whole = (*ticks + TICK_EPSILON).floor();
+            *ticks -= whole;
+            if whole > 0.0 {
+                data.add_income(variant, definition.stats.currency_per_tick * whole);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::currency::Currency;
+
+    #[test]
+    fn income_follows_owned_count_and_cooldown() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(10);
+        let cooldown = data.cooldown(hellmite) as f64;
+        let mut ticks = ProductionTicks::default();
+
+        for _ in 0..100 {
+            ticks.advance(&mut data, cooldown / 10.0);
+        }
+
+        let expected = data.definitions().source(hellmite).stats.currency_per_tick * 100.0;
+        assert_eq!(data.get_currency_by_source(hellmite), expected);
+    }
+
+    #[test]
+    fn fractions_carry_over_between_steps() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(1);
+        let cooldown = data.cooldown(hellmite) as f64;
+        let mut ticks = ProductionTicks::default();
+
+        ticks.advance(&mut data, cooldown * 0.75);
+        assert_eq!(data.get_currency_by_source(hellmite), Currency::ZERO);
+        ticks.advance(&mut data, cooldown * 0.5);
+        assert_eq!(
+            data.get_currency_by_source(hellmite),
+            data.definitions().source(hellmite).stats.currency_per_tick
+        );
+    }
+}
@@ -0,0 +1,547 @@
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+    ui_widgets::observe,
+};
+use std::io;
+
+use crate::{
+    AppState,
+    currency::Currency,
+    data::{AutomatonVariant, GameData},
+    definitions::Definitions,
+    interface::{
+        CONTROL_MUTED_TEXT, CONTROL_TEXT, CONTROL_TEXT_FONT_SIZE, CONTROL_TITLE_FONT_SIZE,
+        FONT_PATH, control_text, dialog_button, dialog_panel, format_duration,
+    },
+    save::{self, SaveCompleted},
+    storage::{self, SaveStore},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const INDEX_NAME: &str = "profiles.json";
+#[cfg(target_arch = "wasm32")]
+const INDEX_NAME: &str = "profiles";
+
+const MAX_NAME_LENGTH: usize = 24;
+
+pub struct ProfilesPlugin;
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ProfileManager::open(""));
+        app.init_resource::<ProfileEditor>();
+        app.add_systems(OnEnter(AppState::ProfileSelect), spawn_profile_picker);
+        app.add_systems(
+            Update,
+            (edit_profile_name, refresh_profile_list)
+                .chain()
+                .run_if(in_state(AppState::ProfileSelect)),
+        );
+        app.add_systems(
+            Update,
+            record_active_profile.run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ProfileMetadata {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub last_played: Option<u64>,
+    #[serde(default)]
+    pub total_entropy: Currency,
+    #[serde(default)]
+    pub highest_unlocked: Option<AutomatonVariant>,
+}
+
+impl ProfileMetadata {
+    fn record(&mut self, data: &GameData, unix_time: u64) {
+        self.last_played = Some(unix_time);
+        self.total_entropy = data.total_generated();
+        self.highest_unlocked = data.highest_unlocked();
+    }
+}
+
+/// The profile currently being played.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveProfile(pub u32);
+
+/// Save slots and their metadata. Each profile keeps its own `SaveStore`
+/// (with backups); the metadata lives in a separate index so the picker can
+/// list profiles without decoding every save.
+#[derive(Resource, Debug)]
+pub struct ProfileManager {
+    root: String,
+    profiles: Vec<ProfileMetadata>,
+}
+
+impl ProfileManager {
+    /// Loads the profile index under `root`, a name prefix that is empty
+    /// outside tests. The first time profiles are used, a save from before
+    /// profiles existed is copied into a profile of its own.
+    pub fn open(root: impl Into<String>) -> Self {
+        let mut manager = Self {
+            root: root.into(),
+            profiles: Vec::new(),
+        };
+        let index = manager.index_store();
+        let saved = index.read(0).ok().flatten();
+        match saved.as_deref().map(serde_json::from_str) {
+            Some(Ok(profiles)) => manager.profiles = profiles,
+            Some(Err(err)) => {
+                warn!("Profile index is unreadable, using its backup: {err}");
+                if let Some(profiles) = index
+                    .read(1)
+                    .ok()
+                    .flatten()
+                    .and_then(|saved| serde_json::from_str(&saved).ok())
+                {
+                    manager.profiles = profiles;
+                }
+            }
+            None => {
+                if let Err(err) = manager.adopt_legacy_save() {
+                    warn!("Could not adopt existing save into a profile: {err}");
+                }
+            }
+        }
+        manager
+    }
+
+    fn index_store(&self) -> SaveStore {
+        SaveStore::new(format!("{}{INDEX_NAME}", self.root))
+    }
+
+    fn adopt_legacy_save(&mut self) -> io::Result<()> {
+        let legacy = SaveStore::new(format!("{}{}", self.root, SaveStore::default().name()));
+        let Some(saved) = legacy.read(0)? else {
+            return Ok(());
+        };
+        let id = self.create("Profile 1")?;
+        legacy.copy_to(&self.store(id))?;
+        if let (Ok(data), Some(profile)) = (save::decode(&saved), self.get_mut(id)) {
+            profile.record(&data, storage::unix_time());
+        }
+        self.persist()
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        let index = serde_json::to_string(&self.profiles).expect("profiles should serialize");
+        self.index_store().write(&index, 1)
+    }
+
+    pub fn list(&self) -> &[ProfileMetadata] {
+        &self.profiles
+    }
+
+    fn get_mut(&mut self, id: u32) -> Option<&mut ProfileMetadata> {
+        self.profiles.iter_mut().find(|profile| profile.id == id)
+    }
+
+    pub fn store(&self, id: u32) -> SaveStore {
+        #[cfg(target_arch = "wasm32")]
+        {
+            SaveStore::new(format!("{}game_data.profile-{id}", self.root))
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            SaveStore::new(format!("{}save_data.profile-{id}.json", self.root))
+        }
+    }
+
+    pub fn create(&mut self, name: impl Into<String>) -> io::Result<u32> {
+        let id = self
+            .profiles
+            .iter()
+            .map(|profile| profile.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.profiles.push(ProfileMetadata {
+            id,
+            name: name.into(),
+            last_played: None,
+            total_entropy: Currency::ZERO,
+            highest_unlocked: None,
+        });
+        self.persist()?;
+        Ok(id)
+    }
+
+    /// "Profile N" with the lowest N no profile is called yet.
+    pub fn unused_name(&self) -> String {
+        (1..)
+            .map(|number| format!("Profile {number}"))
+            .find(|name| self.profiles.iter().all(|profile| &profile.name != name))
+            .expect("some profile number is unused")
+    }
+
+    pub fn rename(&mut self, id: u32, name: impl Into<String>) -> io::Result<()> {
+        let profile = self.get_mut(id).ok_or(io::ErrorKind::NotFound)?;
+        profile.name = name.into();
+        self.persist()
+    }
+
+    pub fn duplicate(&mut self, id: u32) -> io::Result<u32> {
+        let source = self
+            .profiles
+            .iter()
+            .find(|profile| profile.id == id)
+            .cloned()
+            .ok_or(io::ErrorKind::NotFound)?;
+        let copy = self.create(format!("{} (copy)", source.name))?;
+        self.store(id).copy_to(&self.store(copy))?;
+        if let Some(profile) = self.get_mut(copy) {
+            profile.last_played = source.last_played;
+            profile.total_entropy = source.total_entropy;
+            profile.highest_unlocked = source.highest_unlocked;
+        }
+        self.persist()?;
+        Ok(copy)
+    }
+
+    pub fn delete(&mut self, id: u32) -> io::Result<()> {
+        self.store(id).remove()?;
+        self.profiles.retain(|profile| profile.id != id);
+        self.persist()
+    }
+
+    /// Refreshes a profile's metadata from its game data.
+    pub fn record(&mut self, id: u32, data: &GameData, unix_time: u64) -> io::Result<()> {
+        let profile = self.get_mut(id).ok_or(io::ErrorKind::NotFound)?;
+        profile.record(data, unix_time);
+        self.persist()
+    }
+}
+
+fn record_active_profile(
+    mut saves: MessageReader<SaveCompleted>,
+    active: Option<Res<ActiveProfile>>,
+    data: Res<GameData>,
+    mut profiles: ResMut<ProfileManager>,
+) {
+    let (Some(saved), Some(active)) = (saves.read().last(), active) else {
+        return;
+    };
+    if let Err(err) = profiles.record(active.0, &data, saved.unix_time) {
+        warn!("Failed to update profile metadata: {err}");
+    }
+}
+
+#[derive(Resource, Default)]
+struct ProfileEditor {
+    /// The profile being renamed and the name typed so far.
+    renaming: Option<(u32, String)>,
+    /// A profile whose Delete button has been clicked once.
+    confirming_delete: Option<u32>,
+}
+
+#[derive(Component)]
+struct ProfileList;
+
+#[derive(Component, Clone, Copy)]
+enum ProfileButton {
+    Play(u32),
+    Rename(u32),
+    Duplicate(u32),
+    Delete(u32),
+    Create,
+}
+
+fn spawn_profile_picker(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font_handle = asset_server.load(FONT_PATH);
+
+    // Full-screen backdrop so clicks can't reach the portal or rings behind it.
+    commands
+        .spawn((
+            DespawnOnExit(AppState::ProfileSelect),
+            Node {
+                width: percent(100),
+                height: percent(100),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        ))
+        .with_children(|backdrop| {
+            backdrop.spawn(dialog_panel(560.0)).with_children(|picker| {
+                picker.spawn(control_text(
+                    &font_handle,
+                    "Choose a Profile",
+                    CONTROL_TITLE_FONT_SIZE,
+                    CONTROL_TEXT,
+                ));
+                picker.spawn((
+                    ProfileList,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: px(8),
+                        ..default()
+                    },
+                ));
+                picker.spawn(dialog_button(
+                    &font_handle,
+                    "New Profile",
+                    (ProfileButton::Create, observe(on_profile_button)),
+                ));
+            });
+        });
+}
+
+fn refresh_profile_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    profiles: Res<ProfileManager>,
+    editor: Res<ProfileEditor>,
+    definitions: Res<Definitions>,
+    lists: Query<Entity, With<ProfileList>>,
+    spawned: Query<(), Added<ProfileList>>,
+) {
+    if spawned.is_empty() && !profiles.is_changed() && !editor.is_changed() {
+        return;
+    }
+
+    let font_handle = asset_server.load(FONT_PATH);
+    let now = storage::unix_time();
+    for list in &lists {
+        commands.entity(list).despawn_children();
+        commands.entity(list).with_children(|list| {
+            if profiles.list().is_empty() {
+                list.spawn(control_text(
+                    &font_handle,
+                    "No profiles yet.",
+                    CONTROL_TEXT_FONT_SIZE,
+                    CONTROL_MUTED_TEXT,
+                ));
+            }
+            for profile in profiles.list() {
+                spawn_profile_row(list, &font_handle, &definitions, profile, &editor, now);
+            }
+        });
+    }
+}
+
+fn spawn_profile_row(
+    list: &mut ChildSpawnerCommands,
+    font_handle: &Handle<Font>,
+    definitions: &Definitions,
+    profile: &ProfileMetadata,
+    editor: &ProfileEditor,
+    now: u64,
+) {
+    let name = match &editor.renaming {
+        Some((id, typed)) if *id == profile.id => format!("{typed}|"),
+        _ => profile.name.clone(),
+    };
+    let last_played = match profile.last_played {
+        Some(time) => format!("played {} ago", format_duration(now.saturating_sub(time))),
+        None => "never played".to_string(),
+    };
+    let highest = profile
+        .highest_unlocked
+        .map(|variant| format!(", furthest: {}", definitions.source(variant).display_name))
+        .unwrap_or_default();
+    let details = format!("{last_played}, {} Entropy{highest}", profile.total_entropy);
+    let delete_label = if editor.confirming_delete == Some(profile.id) {
+        "Confirm?"
+    } else {
+        "Delete"
+    };
+
+    list.spawn(Node {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        column_gap: px(6),
+        ..default()
+    })
+    .with_children(|row| {
+        row.spawn(Node {
+            flex_grow: 1.0,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        })
+        .with_children(|info| {
+            info.spawn(control_text(
+                font_handle,
+                name,
+                CONTROL_TEXT_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            info.spawn(control_text(
+                font_handle,
+                details,
+                CONTROL_TEXT_FONT_SIZE * 0.75,
+                CONTROL_MUTED_TEXT,
+            ));
+        });
+        for (action, label) in [
+            (ProfileButton::Play(profile.id), "Play"),
+            (ProfileButton::Rename(profile.id), "Rename"),
+            (ProfileButton::Duplicate(profile.id), "Copy"),
+            (ProfileButton::Delete(profile.id), delete_label),
+        ] {
+            row.spawn(dialog_button(
+                font_handle,
+                label,
+                (action, observe(on_profile_button)),
+            ));
+        }
+    });
+}
+
+fn on_profile_button(
+    on: On<Pointer<Click>>,
+    actions: Query<&ProfileButton>,
+    mut commands: Commands,
+    mut profiles: ResMut<ProfileManager>,
+    mut editor: ResMut<ProfileEditor>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(&action) = actions.get(on.event_target()) else {
+        return;
+    };
+    if !matches!(action, ProfileButton::Delete(_)) {
+        editor.confirming_delete = None;
+    }
+
+    let result = match action {
+        ProfileButton::Play(id) => {
+            let store = profiles.store(id);
+            commands.insert_resource(ActiveProfile(id));
+            commands.queue(move |world: &mut World| save::load(world, store));
+            next_state.set(AppState::Playing);
+            Ok(())
+        }
+        ProfileButton::Rename(id) => {
+            let current = profiles.list().iter().find(|profile| profile.id == id);
+            editor.renaming = current.map(|profile| (id, profile.name.clone()));
+            Ok(())
+        }
+        ProfileButton::Duplicate(id) => profiles.duplicate(id).map(|_| ()),
+        ProfileButton::Delete(id) => {
+            if editor.confirming_delete == Some(id) {
+                editor.confirming_delete = None;
+                profiles.delete(id)
+            } else {
+                editor.confirming_delete = Some(id);
+                Ok(())
+            }
+        }
+        ProfileButton::Create => {
+            let name = profiles.unused_name();
+            profiles.create(name).map(|_| ())
+        }
+    };
+    if let Err(err) = result {
+        warn!("Profile action failed: {err}");
+    }
+}
+
+fn edit_profile_name(
+    mut keys: MessageReader<KeyboardInput>,
+    mut editor: ResMut<ProfileEditor>,
+    mut profiles: ResMut<ProfileManager>,
+) {
+    if editor.renaming.is_none() {
+        keys.clear();
+        return;
+    }
+    for key in keys.read() {
+        if key.state != ButtonState::Pressed {
+            continue;
+        }
+        let Some((id, typed)) = editor.renaming.as_mut() else {
+            return;
+        };
+        match &key.logical_key {
+            Key::Enter => {
+                let (id, name) = (*id, typed.trim().to_string());
+                editor.renaming = None;
+                if !name.is_empty()
+                    && let Err(err) = profiles.rename(id, name)
+                {
+                    warn!("Failed to rename profile: {err}");
+                }
+            }
+            Key::Escape => editor.renaming = None,
+            Key::Backspace => {
+                typed.pop();
+            }
+            Key::Space if typed.chars().count() < MAX_NAME_LENGTH => typed.push(' '),
+            Key::Character(text)
+                if typed.chars().count() + text.chars().count() <= MAX_NAME_LENGTH =>
+            {
+                typed.push_str(text);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::ScratchStore;
+
+    const V1_MIDGAME: &str = include_str!("../tests/fixtures/saves/v1_midgame.json");
+
+    #[test]
+    fn existing_save_is_adopted_as_the_first_profile() {
+        let scratch = ScratchStore::new("profiles-adopt");
+        scratch.store.write(V1_MIDGAME, 0).unwrap();
+
+        let profiles = ProfileManager::open(scratch.root());
+
+        let [profile] = profiles.list() else {
+            panic!("expected exactly one profile");
+        };
+        assert_eq!(profile.name, "Profile 1");
+        assert_eq!(profile.total_entropy, Currency::new(1_106));
+        assert_eq!(profile.highest_unlocked, Some(AutomatonVariant::Abyssopod));
+        let restored = save::restore(&profiles.store(profile.id)).unwrap();
+        assert_eq!(restored.get_currency(), Currency::new(80));
+    }
+
+    #[test]
+    fn profiles_can_be_created_renamed_duplicated_and_deleted() {
+        let scratch = ScratchStore::new("profiles-manage");
+        let mut profiles = ProfileManager::open(scratch.root());
+        assert!(profiles.list().is_empty());
+
+        let first = profiles.create("Early game").unwrap();
+        profiles.store(first).write(V1_MIDGAME, 0).unwrap();
+        profiles.rename(first, "Hellmite farm").unwrap();
+        let copy = profiles.duplicate(first).unwrap();
+        profiles.delete(first).unwrap();
+
+        let reopened = ProfileManager::open(scratch.root());
+        let names: Vec<_> = reopened.list().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Hellmite farm (copy)"]);
+        assert_eq!(reopened.store(first).read(0).unwrap(), None);
+        assert!(save::restore(&reopened.store(copy)).is_ok());
+    }
+
+    #[test]
+    fn new_profiles_take_the_lowest_unused_number() {
+        let scratch = ScratchStore::new("profiles-numbering");
+        let mut profiles = ProfileManager::open(scratch.root());
+        let first = profiles.create(profiles.unused_name()).unwrap();
+        profiles.create(profiles.unused_name()).unwrap();
+        profiles.delete(first).unwrap();
+
+        profiles.create(profiles.unused_name()).unwrap();
+
+        let names: Vec<_> = profiles.list().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Profile 2", "Profile 1"]);
+        assert_eq!(profiles.unused_name(), "Profile 3");
+    }
+}
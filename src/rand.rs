@@ -1,76 +1,256 @@
-use std::cell::Cell;
+use bevy::prelude::*;
 use std::ops::Range;
 
-thread_local! {
-    static RNG_STATE: Cell<u64> = const { Cell::new(0) };
-}
+/// Used when the seed would leave xorshift stuck at zero.
+const FALLBACK_SEED: u64 = 0x9E3779B97F4A7C15;
 
-fn seed() -> u64 {
+fn entropy_seed() -> u64 {
     let mut buf = [0u8; 8];
     if getrandom::fill(&mut buf).is_err() {
-        return 0x9E3779B97F4A7C15;
+        return FALLBACK_SEED;
     }
     u64::from_le_bytes(buf)
 }
 
-fn next_u64() -> u64 {
-    RNG_STATE.with(|s| {
-        let mut x = s.get();
-        if x == 0 {
-            x = seed();
-            if x == 0 {
-                x = 0x9E3779B97F4A7C15;
-            }
+/// SplitMix64, to spread nearby seeds (such as 1, 2, 3) far apart.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+/// A small xorshift generator. Not for anything security related.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn seeded(seed: u64) -> Self {
+        let state = mix(seed);
+        Self {
+            state: if state == 0 { FALLBACK_SEED } else { state },
         }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
         x ^= x << 13;
         x ^= x >> 7;
         x ^= x << 17;
-        s.set(x);
+        self.state = x;
         x
-    })
+    }
+
+    /// Uniform in `0..bound` without modulo bias (Lemire's method). Zero for
+    /// an empty bound.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = self.next_u64() as u128 * bound as u128;
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    /// Uniform in `0..1`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `range`. An empty range yields its start.
+    pub fn random_range<T: RandomRange>(&mut self, range: Range<T>) -> T {
+        T::sample(self, range)
+    }
+
+    /// An element of `items`, each as likely as its share of the total
+    /// weight. `None` if no element has a positive weight.
+    pub fn choose_weighted<'a, T>(
+        &mut self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f64,
+    ) -> Option<&'a T> {
+        let weight = |item: &T| weight(item).max(0.0);
+        let total: f64 = items.iter().map(weight).sum();
+        if total <= 0.0 || !total.is_finite() {
+            return None;
+        }
+        let mut target = self.unit() * total;
+        for item in items {
+            target -= weight(item);
+            if target < 0.0 {
+                return Some(item);
+            }
+        }
+        // Rounding can leave a sliver past the last positive weight.
+        items.iter().rev().find(|item| weight(item) > 0.0)
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Normally distributed around `mean` (Box-Muller).
+    pub fn normal(&mut self, mean: f64, std_dev: f64) -> f64 {
+        // `1 - unit` is in `(0, 1]`, keeping the logarithm finite.
+        let radius = (-2.0 * (1.0 - self.unit()).ln()).sqrt();
+        let angle = std::f64::consts::TAU * self.unit();
+        mean + std_dev * radius * angle.cos()
+    }
 }
 
-pub trait RandomRange {
-    fn random_range(range: Range<Self>) -> Self
-    where
-        Self: Sized;
+pub trait RandomRange: Sized {
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self;
 }
 
 impl RandomRange for f32 {
-    fn random_range(range: Range<f32>) -> f32 {
-        let unit = (next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+    fn sample(rng: &mut Rng, range: Range<f32>) -> f32 {
+        if range.is_empty() {
+            return range.start;
+        }
+        let unit = (rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
         range.start + unit * (range.end - range.start)
     }
 }
 
 impl RandomRange for f64 {
-    fn random_range(range: Range<f64>) -> f64 {
-        let unit = (next_u64() >> 11) as f64 / (1u64 << 53) as f64;
-        range.start + unit * (range.end - range.start)
+    fn sample(rng: &mut Rng, range: Range<f64>) -> f64 {
+        if range.is_empty() {
+            return range.start;
+        }
+        range.start + rng.unit() * (range.end - range.start)
     }
 }
 
-impl RandomRange for usize {
-    fn random_range(range: Range<usize>) -> usize {
-        let span = range.end - range.start;
-        range.start + (next_u64() as usize) % span
-    }
+macro_rules! impl_random_range_for_integers {
+    ($($int:ty),*) => {$(
+        impl RandomRange for $int {
+            fn sample(rng: &mut Rng, range: Range<$int>) -> $int {
+                if range.is_empty() {
+                    return range.start;
+                }
+                let span = range.end.abs_diff(range.start) as u64;
+                range.start.wrapping_add(rng.below(span) as $int)
+            }
+        }
+    )*};
+}
+
+impl_random_range_for_integers!(usize, u32, u64, i32);
+
+/// Which part of the game a random draw is for. Each has its own generator,
+/// so cosmetic draws never shift gameplay outcomes for a given seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// Anything that changes `GameData`, such as crits and rift rewards.
+    Gameplay,
+    /// Animation, placement and audio choices.
+    Visuals,
+}
+
+/// The game's random numbers, split into streams derived from one seed so a
+/// run can be replayed by seeding it the same way.
+#[derive(Resource, Debug, Clone)]
+pub struct RandomSource {
+    seed: u64,
+    gameplay: Rng,
+    visuals: Rng,
 }
 
-impl RandomRange for u32 {
-    fn random_range(range: Range<u32>) -> u32 {
-        let span = range.end - range.start;
-        range.start + (next_u64() as u32) % span
+impl RandomSource {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            gameplay: Rng::seeded(seed),
+            visuals: Rng::seeded(mix(seed ^ 0x5649_5355_414C_5321)),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut Rng {
+        match stream {
+            RngStream::Gameplay => &mut self.gameplay,
+            RngStream::Visuals => &mut self.visuals,
+        }
+    }
+
+    pub fn gameplay(&mut self) -> &mut Rng {
+        self.stream(RngStream::Gameplay)
+    }
+
+    pub fn visuals(&mut self) -> &mut Rng {
+        self.stream(RngStream::Visuals)
     }
 }
 
-impl RandomRange for i32 {
-    fn random_range(range: Range<i32>) -> i32 {
-        let span = (range.end - range.start) as u32;
-        range.start + ((next_u64() as u32) % span) as i32
+impl Default for RandomSource {
+    fn default() -> Self {
+        Self::seeded(entropy_seed())
     }
 }
 
-pub fn random_range<T: RandomRange>(range: Range<T>) -> T {
-    T::random_range(range)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_draws_and_streams_are_independent() {
+        let mut a = RandomSource::seeded(7);
+        let mut b = RandomSource::seeded(7);
+        let _ = b.visuals().next_u64();
+
+        assert_eq!(a.gameplay().next_u64(), b.gameplay().next_u64());
+        assert_ne!(a.gameplay().next_u64(), a.visuals().next_u64());
+    }
+
+    #[test]
+    fn integer_ranges_are_unbiased_and_allow_empty_ranges() {
+        let mut rng = Rng::seeded(1);
+        assert_eq!(rng.random_range(5..5), 5);
+        assert_eq!(rng.random_range(-3i32..-2), -3);
+
+        let mut counts = [0u32; 3];
+        for _ in 0..30_000 {
+            counts[rng.random_range(0usize..3)] += 1;
+        }
+        assert!(counts.iter().all(|&count| (9_500..10_500).contains(&count)));
+    }
+
+    #[test]
+    fn weighted_choice_skips_zero_weights() {
+        let mut rng = Rng::seeded(2);
+        let items = [("never", 0.0), ("always", 1.0)];
+        for _ in 0..100 {
+            assert_eq!(
+                rng.choose_weighted(&items, |item| item.1).unwrap().0,
+                "always"
+            );
+        }
+        assert!(rng.choose_weighted(&items[..1], |item| item.1).is_none());
+    }
+
+    #[test]
+    fn shuffle_keeps_every_element_and_normal_centres_on_its_mean() {
+        let mut rng = Rng::seeded(3);
+        let mut items: Vec<u32> = (0..50).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..50).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+
+        let mean = (0..10_000).map(|_| rng.normal(5.0, 2.0)).sum::<f64>() / 10_000.0;
+        assert!((mean - 5.0).abs() < 0.1);
+    }
 }
@@ -0,0 +1,182 @@
+use bevy::{
+    light::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+};
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    AppState, audio,
+    buffs::{Buff, BuffEffect, BuffStacking},
+    data::GameData,
+    rand::{RandomSource, Rng},
+};
+
+/// Seconds between rifts, drawn uniformly from this range.
+const RIFT_DELAY: std::ops::Range<f32> = 60.0..180.0;
+/// Seconds a rift stays open before closing unclicked.
+const RIFT_LIFETIME: f32 = 12.0;
+/// Distance of a rift from the portal, drawn uniformly from this range.
+const RIFT_DISTANCE: std::ops::Range<f32> = 4.0..6.0;
+
+#[derive(Debug, Clone, Copy)]
+enum RiftReward {
+    Buff {
+        name: &'static str,
+        effect: BuffEffect,
+        multiplier: f64,
+        duration: f32,
+        stacking: BuffStacking,
+    },
+    /// Pays this many seconds of current production at once.
+    LumpSum { seconds: f64 },
+}
+
+const RIFT_REWARDS: [RiftReward; 4] = [
+    RiftReward::Buff {
+        name: "Rift Surge",
+        effect: BuffEffect::Production,
+        multiplier: 7.0,
+        duration: 30.0,
+        stacking: BuffStacking::Refresh,
+    },
+    RiftReward::Buff {
+        name: "Frenzied Touch",
+        effect: BuffEffect::Clicks,
+        multiplier: 25.0,
+        duration: 15.0,
+        stacking: BuffStacking::Extend,
+    },
+    RiftReward::Buff {
+        name: "Echoing Hum",
+        effect: BuffEffect::Production,
+        multiplier: 1.5,
+        duration: 60.0,
+        stacking: BuffStacking::Stack,
+    },
+    RiftReward::LumpSum { seconds: 60.0 },
+];
+
+/// Randomly opens clickable rifts around the portal that grant buffs or
+/// lumps of Entropy.
+pub struct RiftsPlugin;
+
+impl Plugin for RiftsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), start_rift_scheduler);
+        app.add_systems(
+            Update,
+            (schedule_rifts, update_rifts).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+/// Counts down to the next rift.
+#[derive(Resource)]
+struct RiftScheduler(Timer);
+
+#[derive(Component)]
+struct Rift {
+    lifetime: Timer,
+}
+
+fn next_rift_timer(rng: &mut Rng) -> Timer {
+    Timer::from_seconds(rng.random_range(RIFT_DELAY), TimerMode::Once)
+}
+
+fn start_rift_scheduler(mut commands: Commands, mut random: ResMut<RandomSource>) {
+    commands.insert_resource(RiftScheduler(next_rift_timer(random.gameplay())));
+}
+
+fn schedule_rifts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut scheduler: ResMut<RiftScheduler>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    rifts: Query<(), With<Rift>>,
+    mut random: ResMut<RandomSource>,
+) {
+    if !scheduler.0.tick(time.delta()).is_finished() {
+        return;
+    }
+    scheduler.0 = next_rift_timer(random.gameplay());
+    if !rifts.is_empty() {
+        return;
+    }
+
+    let rng = random.visuals();
+    let angle = rng.random_range(0.0..std::f32::consts::TAU);
+    let distance = rng.random_range(RIFT_DISTANCE);
+    let height = rng.random_range(-1.0..1.0);
+    commands
+        .spawn((
+            Rift {
+                lifetime: Timer::from_seconds(RIFT_LIFETIME, TimerMode::Once),
+            },
+            Mesh3d(meshes.add(Torus {
+                minor_radius: 0.08,
+                major_radius: 0.35,
+            })),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                emissive: Color::srgb(0., 4., 6.).into(),
+                base_color: Srgba::rgb_u8(0, 200, 255).into(),
+                #[cfg(target_arch = "wasm32")]
+                unlit: true,
+                alpha_mode: AlphaMode::Opaque,
+                ..default()
+            })),
+            Transform::from_xyz(distance * angle.cos(), height, distance * angle.sin()),
+            NotShadowCaster,
+            NotShadowReceiver,
+            DespawnOnExit(AppState::Playing),
+        ))
+        .observe(click_on_rift);
+}
+
+fn update_rifts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut rifts: Query<(Entity, &mut Rift, &mut Transform)>,
+) {
+    for (entity, mut rift, mut transform) in &mut rifts {
+        if rift.lifetime.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Pulse, and shrink away over the last second.
+        let fade = rift.lifetime.remaining_secs().min(1.0);
+        let pulse = 1.0 + 0.15 * (rift.lifetime.elapsed_secs() * 6.0).sin();
+        transform.scale = Vec3::splat(pulse * fade);
+        transform.rotate_y(time.delta_secs() * 2.0);
+        transform.rotate_x(time.delta_secs() * 1.3);
+    }
+}
+
+fn click_on_rift(
+    on: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut game_data: ResMut<GameData>,
+    audio_state: Res<audio::AudioState>,
+    interaction: Res<AudioChannel<audio::InteractionChannel>>,
+    mut random: ResMut<RandomSource>,
+) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+
+    audio::play_pickup_sound(&interaction, &audio_state);
+
+    match RIFT_REWARDS[random.gameplay().random_range(0..RIFT_REWARDS.len())] {
+        RiftReward::Buff {
+            name,
+            effect,
+            multiplier,
+            duration,
+            stacking,
+        } => game_data.add_buff(Buff::new(name, effect, multiplier, duration), stacking),
+        RiftReward::LumpSum { seconds } => {
+            game_data.grant_lump_sum(seconds);
+        }
+    }
+    commands.entity(on.event_target()).despawn();
+}
@@ -0,0 +1,533 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bevy::{ecs::system::SystemParam, prelude::*, window::WindowCloseRequested};
+use serde_json::{Map, Value};
+
+use crate::{
+    AppState,
+    data::{GameData, OfflineProgressSettings},
+    definitions::Definitions,
+    storage::{self, SaveStore},
+};
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveSettings>();
+        app.init_resource::<OfflineProgressSettings>();
+        app.add_message::<SaveFailed>();
+        app.add_message::<SaveCompleted>();
+        // Nothing is written while the player decides how to recover a damaged
+        // save, so the placeholder data can't rotate the good backups away.
+        app.add_systems(
+            Update,
+            flush_on_schedule
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<SaveRecovery>)),
+        );
+        app.add_systems(
+            Last,
+            flush_on_exit
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<SaveRecovery>)),
+        );
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct SaveSettings {
+    /// Seconds between flushes of routine changes such as income ticks.
+    pub interval: f32,
+    /// How many previous saves to keep alongside the live one.
+    pub backups: usize,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            interval: 5.0,
+            backups: 3,
+        }
+    }
+}
+
+/// Written when a save could not be persisted, so the interface can tell the
+/// player their progress is at risk.
+#[derive(Message, Debug, Clone)]
+pub struct SaveFailed {
+    pub reason: String,
+}
+
+/// Written after game data has been persisted.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct SaveCompleted {
+    pub unix_time: u64,
+}
+
+/// Present while the live save failed to load and the player has not yet
+/// chosen between starting fresh, loading a backup or quitting.
+#[derive(Resource, Debug)]
+pub struct SaveRecovery {
+    pub error: SaveError,
+    /// The newest backup that decodes, with its generation number.
+    pub backup: Option<(usize, GameData)>,
+}
+
+/// Version stamped into every save document. Bump it and append a migration to
+/// `MIGRATIONS` whenever the persisted layout of `GameData` changes.
+pub const SAVE_VERSION: u64 = 9;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` document to version `n + 2`.
+const MIGRATIONS: [Migration; (SAVE_VERSION - 1) as usize] = [
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+];
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnknownVersion(u64),
+    Invalid(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "save data could not be read: {err}"),
+            SaveError::Parse(err) => write!(f, "save data could not be parsed: {err}"),
+            SaveError::UnknownVersion(version) => {
+                write!(f, "save data has unsupported version {version}")
+            }
+            SaveError::Invalid(reason) => write!(f, "save data is invalid: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Parse(err)
+    }
+}
+
+pub fn encode(data: &GameData) -> String {
+    let mut document = serde_json::to_value(data).expect("game data should serialize");
+    if let Value::Object(object) = &mut document {
+        object.insert("version".into(), SAVE_VERSION.into());
+    }
+    document.to_string()
+}
+
+pub fn decode(saved: &str) -> Result<GameData, SaveError> {
+    let document = migrate(serde_json::from_str(saved)?)?;
+    let data: GameData = serde_json::from_value(document)?;
+    data.validate().map_err(SaveError::Invalid)?;
+    Ok(data)
+}
+
+/// Prefix identifying a save code, bumped if the code layout itself changes.
+const EXPORT_PREFIX: &str = "FDI1.";
+
+/// Encodes a save as a single line of text that can be pasted into another
+/// install: the save document followed by its CRC-32, in URL-safe base64.
+pub fn export_code(data: &GameData) -> String {
+    let mut payload = encode(data).into_bytes();
+    let checksum = crc32(&payload);
+    payload.extend_from_slice(&checksum.to_be_bytes());
+    format!("{EXPORT_PREFIX}{}", URL_SAFE_NO_PAD.encode(payload))
+}
+
+/// Decodes a code from `export_code`, running it through the same migration
+/// and validation as a save file.
+pub fn import_code(code: &str) -> Result<GameData, SaveError> {
+    let invalid = |reason: &str| SaveError::Invalid(reason.to_string());
+    let encoded = code
+        .trim()
+        .strip_prefix(EXPORT_PREFIX)
+        .ok_or_else(|| invalid("not a save code"))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| invalid("save code is not valid base64"))?;
+    let (document, checksum) = payload
+        .split_last_chunk::<4>()
+        .ok_or_else(|| invalid("save code is truncated"))?;
+    if crc32(document) != u32::from_be_bytes(*checksum) {
+        return Err(invalid("save code checksum does not match"));
+    }
+    let document =
+        std::str::from_utf8(document).map_err(|_| invalid("save code is not valid text"))?;
+    decode(document)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Loads the live save. A missing save is a new player, unless a backup
+/// survived a crash between rotating and renaming. A save that can't be used
+/// is quarantined before the error is returned, so the next write can't
+/// destroy the evidence.
+pub fn restore(store: &SaveStore) -> Result<GameData, SaveError> {
+    let result = match store.read(0) {
+        Ok(Some(saved)) => decode(&saved),
+        Ok(None) => {
+            return Ok(newest_backup(store)
+                .map(|(_, data)| data)
+                .unwrap_or_default());
+        }
+        Err(err) => Err(err.into()),
+    };
+
+    if let Err(err) = &result {
+        let transient =
+            matches!(err, SaveError::Io(io) if io.kind() != std::io::ErrorKind::InvalidData);
+        if !transient {
+            match store.quarantine(storage::unix_time()) {
+                Ok(quarantined) => warn!("Moved unreadable save to {quarantined}: {err}"),
+                Err(move_err) => warn!("Could not quarantine unreadable save: {move_err}"),
+            }
+        }
+    }
+    result
+}
+
+/// The newest backup generation that decodes.
+pub fn newest_backup(store: &SaveStore) -> Option<(usize, GameData)> {
+    let mut generation = 1;
+    while let Ok(Some(saved)) = store.read(generation) {
+        match decode(&saved) {
+            Ok(data) => return Some((generation, data)),
+            Err(err) => warn!("Skipping save backup {generation}: {err}"),
+        }
+        generation += 1;
+    }
+    None
+}
+
+/// Restores `store` into the world and credits time spent away. When the
+/// live save can't be used the player starts on placeholder data and
+/// `SaveRecovery` asks what to do next.
+pub fn load(world: &mut World, store: SaveStore) {
+    match restore(&store) {
+        Ok(mut data) => {
+            if let Some(definitions) = world.get_resource::<Definitions>() {
+                data.set_definitions(definitions.clone());
+            }
+            let settings = world
+                .get_resource::<OfflineProgressSettings>()
+                .cloned()
+                .unwrap_or_default();
+            if let Some(report) = data.apply_offline_progress(storage::unix_time(), &settings) {
+                world.insert_resource(report);
+            }
+            world.insert_resource(data);
+        }
+        Err(error) => {
+            error!("Failed to restore save: {error}");
+            world.insert_resource(GameData::default());
+            world.insert_resource(SaveRecovery {
+                backup: newest_backup(&store),
+                error,
+            });
+        }
+    }
+    world.insert_resource(store);
+}
+
+#[derive(SystemParam)]
+struct SaveOutcomes<'w> {
+    completed: MessageWriter<'w, SaveCompleted>,
+    failed: MessageWriter<'w, SaveFailed>,
+}
+
+fn flush(
+    data: &mut ResMut<GameData>,
+    store: &SaveStore,
+    settings: &SaveSettings,
+    outcomes: &mut SaveOutcomes,
+) {
+    // Save bookkeeping is not a change other systems care about.
+    let data = data.bypass_change_detection();
+    let unix_time = storage::unix_time();
+    data.set_last_saved(unix_time);
+    match store.write(&encode(data), settings.backups) {
+        Ok(()) => {
+            data.mark_saved();
+            outcomes.completed.write(SaveCompleted { unix_time });
+        }
+        Err(err) => {
+            warn!("Failed to save game data: {err}");
+            outcomes.failed.write(SaveFailed {
+                reason: err.to_string(),
+            });
+            data.mark_save_failed();
+        }
+    }
+}
+
+fn flush_on_schedule(
+    time: Res<Time>,
+    settings: Res<SaveSettings>,
+    store: Res<SaveStore>,
+    mut since_flush: Local<f32>,
+    mut data: ResMut<GameData>,
+    mut outcomes: SaveOutcomes,
+) {
+    *since_flush += time.delta_secs();
+    let due = *since_flush >= settings.interval && data.is_dirty();
+    if data.is_save_requested() || due {
+        flush(&mut data, &store, &settings, &mut outcomes);
+        *since_flush = 0.0;
+    }
+}
+
+fn flush_on_exit(
+    mut exits: MessageReader<AppExit>,
+    mut close_requests: MessageReader<WindowCloseRequested>,
+    settings: Res<SaveSettings>,
+    store: Res<SaveStore>,
+    mut data: ResMut<GameData>,
+    mut outcomes: SaveOutcomes,
+) {
+    let exiting = exits.read().count() > 0;
+    let closing = close_requests.read().count() > 0;
+    if (exiting || closing) && data.is_dirty() {
+        flush(&mut data, &store, &settings, &mut outcomes);
+    }
+}
+
+/// Upgrades a parsed save document of any known version to `SAVE_VERSION`.
+/// Documents written before versioning was introduced have no `version` field
+/// and are treated as version 1.
+fn migrate(mut document: Value) -> Result<Value, SaveError> {
+    let Value::Object(object) = &mut document else {
+        // Let serde report the shape mismatch.
+        return Ok(document);
+    };
+
+    let version = match object.get("version") {
+        None => 1,
+        Some(version) => version.as_u64().ok_or(SaveError::UnknownVersion(0))?,
+    };
+    if version == 0 || version > SAVE_VERSION {
+        return Err(SaveError::UnknownVersion(version));
+    }
+
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(object);
+    }
+    object.insert("version".into(), SAVE_VERSION.into());
+
+    Ok(document)
+}
+
+/// v2 introduced the `version` field itself; the layout is otherwise unchanged.
+fn v1_to_v2(_document: &mut Map<String, Value>) {}
+
+/// v3 writes Entropy amounts too large for a `u64` as strings like `"1.5e21"`.
+/// Smaller amounts are still plain numbers, so older documents read as-is.
+fn v2_to_v3(_document: &mut Map<String, Value>) {}
+
+/// v4 added `prestige_count` and `lucidity`, which default to zero.
+fn v3_to_v4(_document: &mut Map<String, Value>) {}
+
+/// v5 added `upgrades`, which defaults to none owned.
+fn v4_to_v5(_document: &mut Map<String, Value>) {}
+
+/// v6 added `portal_clicks` and `achievements`, which default to none.
+fn v5_to_v6(_document: &mut Map<String, Value>) {}
+
+/// v7 added `portal_crits` and `best_click`, which default to zero.
+fn v6_to_v7(_document: &mut Map<String, Value>) {}
+
+/// v8 added `buffs`, which defaults to none active.
+fn v7_to_v8(_document: &mut Map<String, Value>) {}
+
+/// v9 added `auto_buyers`, which defaults to every auto-buyer switched off.
+fn v8_to_v9(_document: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{currency::Currency, data::AutomatonVariant, storage::tests::ScratchStore};
+
+    const V1_FRESH: &str = include_str!("../tests/fixtures/saves/v1_fresh.json");
+    const V1_MIDGAME: &str = include_str!("../tests/fixtures/saves/v1_midgame.json");
+    const V1_LATE: &str = include_str!("../tests/fixtures/saves/v1_late.json");
+
+    #[test]
+    fn v1_fresh_fixture_loads_with_defaults() {
+        let data = decode(V1_FRESH).expect("v1 fresh save should load");
+
+        assert_eq!(data.get_currency(), Currency::new(0));
+        assert_eq!(
+            data.get_quantity_owned_by_source(AutomatonVariant::Hellmite),
+            0
+        );
+        assert_eq!(data.audio_settings.volume, 0.25);
+        assert!(data.audio_settings.play_pickup);
+    }
+
+    #[test]
+    fn v1_midgame_fixture_keeps_progress() {
+        let data = decode(V1_MIDGAME).expect("v1 midgame save should load");
+
+        assert_eq!(data.get_currency(), Currency::new(80));
+        assert_eq!(
+            data.get_quantity_owned_by_source(AutomatonVariant::Hellmite),
+            20
+        );
+        assert_eq!(
+            data.get_quantity_owned_by_source(AutomatonVariant::Abyssopod),
+            2
+        );
+        assert_eq!(
+            data.get_currency_by_source(AutomatonVariant::Portal),
+            Currency::new(482)
+        );
+        assert_eq!(
+            data.get_currency_by_source(AutomatonVariant::Hellmite),
+            Currency::new(584)
+        );
+        assert_eq!(
+            data.get_currency_by_source(AutomatonVariant::Abyssopod),
+            Currency::new(40)
+        );
+        assert_eq!(data.audio_settings.volume, 0.0);
+        assert!(!data.audio_settings.play_pickup);
+    }
+
+    #[test]
+    fn v1_late_fixture_keeps_progress() {
+        let data = decode(V1_LATE).expect("v1 late save should load");
+
+        assert_eq!(data.get_currency(), Currency::new(1_893_214));
+        assert_eq!(
+            data.get_quantity_owned_by_source(AutomatonVariant::Hellmite),
+            41
+        );
+        assert_eq!(
+            data.get_quantity_owned_by_source(AutomatonVariant::Lorgner),
+            3
+        );
+        assert_eq!(
+            data.get_currency_by_source(AutomatonVariant::GazingHoku),
+            Currency::new(1_204_480)
+        );
+        assert_eq!(data.get_level(AutomatonVariant::Hellmite), 2);
+        assert_eq!(data.get_level(AutomatonVariant::Abyssopod), 1);
+        assert_eq!(data.audio_settings.volume, 0.6);
+    }
+
+    #[test]
+    fn encoded_saves_round_trip_at_current_version() {
+        for fixture in [V1_FRESH, V1_MIDGAME, V1_LATE] {
+            let data = decode(fixture).unwrap();
+            let encoded = encode(&data);
+
+            let document: Value = serde_json::from_str(&encoded).unwrap();
+            assert_eq!(document["version"], SAVE_VERSION);
+            assert_eq!(decode(&encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn saves_from_newer_versions_are_rejected() {
+        let saved = format!(r#"{{"version":{},"currency":5}}"#, SAVE_VERSION + 1);
+
+        assert!(matches!(
+            decode(&saved),
+            Err(SaveError::UnknownVersion(version)) if version == SAVE_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn corrupt_saves_are_quarantined_and_backups_offered() {
+        let scratch = ScratchStore::new("restore-corrupt");
+        let store = &scratch.store;
+        store.write(V1_MIDGAME, 3).unwrap();
+        store.write(V1_LATE, 3).unwrap();
+        store.write("{\"currency\":", 3).unwrap();
+
+        assert!(matches!(restore(store), Err(SaveError::Parse(_))));
+        assert_eq!(store.read(0).unwrap(), None);
+
+        let (generation, backup) = newest_backup(store).expect("backup should decode");
+        assert_eq!(generation, 1);
+        assert_eq!(backup.get_currency(), Currency::new(1_893_214));
+    }
+
+    #[test]
+    fn missing_live_save_restores_from_backup() {
+        let scratch = ScratchStore::new("restore-missing");
+        let store = &scratch.store;
+        store.write(V1_MIDGAME, 3).unwrap();
+        store.write(V1_LATE, 3).unwrap();
+        store.quarantine(0).unwrap();
+
+        assert_eq!(restore(store).unwrap().get_currency(), Currency::new(80));
+    }
+
+    #[test]
+    fn saves_that_fail_validation_are_rejected() {
+        let portal = r#"{"currency":5,"owned_by_type":{"Portal":3},"income_by_type":{}}"#;
+        let too_many =
+            r#"{"currency":5,"owned_by_type":{"Hellmite":1000000000001},"income_by_type":{}}"#;
+
+        assert!(matches!(decode(portal), Err(SaveError::Invalid(_))));
+        assert!(matches!(decode(too_many), Err(SaveError::Invalid(_))));
+    }
+
+    #[test]
+    fn save_codes_round_trip() {
+        let data = decode(V1_LATE).unwrap();
+
+        let code = export_code(&data);
+
+        assert!(code.starts_with(EXPORT_PREFIX));
+        assert_eq!(import_code(&format!("  {code}\n")).unwrap(), data);
+    }
+
+    #[test]
+    fn damaged_save_codes_are_rejected() {
+        let code = export_code(&decode(V1_MIDGAME).unwrap());
+        let mut damaged = code.clone().into_bytes();
+        let middle = damaged.len() / 2;
+        damaged[middle] = if damaged[middle] == b'A' { b'B' } else { b'A' };
+
+        assert!(matches!(
+            import_code(std::str::from_utf8(&damaged).unwrap()),
+            Err(SaveError::Invalid(_))
+        ));
+        assert!(matches!(
+            import_code(&code[..code.len() - 8]),
+            Err(SaveError::Invalid(_))
+        ));
+        assert!(matches!(import_code("hello"), Err(SaveError::Invalid(_))));
+    }
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use crate::{
+    currency::Currency,
+    data::{AutomatonVariant, GameData},
+    production::ProductionTicks,
+    rand::Rng,
+};
+
+/// Something a strategy can spend Entropy (or automatons, for levels) on.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Purchase {
+    Source(AutomatonVariant),
+    LevelUp(AutomatonVariant),
+    Upgrade(String),
+}
+
+impl Purchase {
+    /// What the purchase costs right now, in Entropy or, for levels, in
+    /// automatons of that variant.
+    pub fn cost(&self, data: &GameData) -> Option<Currency> {
+        match self {
+            Purchase::Source(variant) => Some(data.get_cost_to_add_source(*variant)),
+            Purchase::LevelUp(variant) => data.cost_to_level_up(*variant).map(Currency::new),
+            Purchase::Upgrade(id) => data.definitions().upgrade(id).map(|upgrade| upgrade.cost),
+        }
+    }
+
+    /// Makes the purchase, returning whether it went through.
+    pub fn apply(&self, data: &mut GameData) -> bool {
+        match self {
+            Purchase::Source(variant) => data.purchase_sources(*variant, 1),
+            Purchase::LevelUp(variant) => data.level_up(*variant),
+            Purchase::Upgrade(id) => data.purchase_upgrade(id),
+        }
+    }
+}
+
+/// Decides what a simulated player buys next.
+pub trait Strategy {
+    /// The purchase to wait for, or `None` to let a step pass before asking
+    /// again. A purchase is made as soon as it is affordable.
+    fn next(&mut self, data: &GameData) -> Option<Purchase>;
+}
+
+/// Always buys the cheapest unlocked automaton.
+pub struct GreedyCheapest;
+
+impl Strategy for GreedyCheapest {
+    fn next(&mut self, data: &GameData) -> Option<Purchase> {
+        unlocked_variants(data)
+            .min_by(|&a, &b| {
+                data.get_cost_to_add_source(a)
+                    .partial_cmp(&data.get_cost_to_add_source(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(Purchase::Source)
+    }
+}
+
+/// Buys the unlocked automaton that earns its price back soonest. Before
+/// anything produces, falls back to the cheapest one.
+pub struct BestPayback;
+
+impl Strategy for BestPayback {
+    fn next(&mut self, data: &GameData) -> Option<Purchase> {
+        unlocked_variants(data)
+            .filter_map(|variant| Some((variant, data.payback_seconds(variant)?)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(variant, _)| Purchase::Source(variant))
+            .or_else(|| GreedyCheapest.next(data))
+    }
+}
+
+/// Makes a fixed list of purchases in order, waiting on each until it can be
+/// made, then idles.
+pub struct Scripted {
+    steps: std::vec::IntoIter<Purchase>,
+    current: Option<Purchase>,
+}
+
+impl Scripted {
+    pub fn new(steps: Vec<Purchase>) -> Self {
+        let mut steps = steps.into_iter();
+        let current = steps.next();
+        Self { steps, current }
+    }
+}
+
+impl Strategy for Scripted {
+    fn next(&mut self, _data: &GameData) -> Option<Purchase> {
+        let purchase = self.current.take();
+        self.current = self.steps.next();
+        purchase
+    }
+}
+
+fn unlocked_variants(data: &GameData) -> impl Iterator<Item = AutomatonVariant> + '_ {
+    data.definitions()
+        .automatons()
+        .map(|definition| definition.variant)
+        .filter(|&variant| data.prerequisites_met(variant))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationSettings {
+    /// Seconds per step.
+    pub timestep: f64,
+    /// Portal clicks the simulated player makes per second.
+    pub clicks_per_second: f64,
+    /// Seconds between samples of the currency curve.
+    pub sample_interval: f64,
+    /// Seed for crit rolls, so a run can be repeated exactly.
+    pub seed: u64,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self {
+            timestep: 0.1,
+            clicks_per_second: 2.0,
+            sample_interval: 60.0,
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct UnlockTime {
+    pub variant: AutomatonVariant,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CurrencySample {
+    pub seconds: f64,
+    pub currency: Currency,
+    pub generated: Currency,
+    pub rate_per_second: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct PurchaseRecord {
+    pub seconds: f64,
+    pub purchase: Purchase,
+    pub cost: Currency,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct SimulationReport {
+    pub unlocks: Vec<UnlockTime>,
+    pub currency: Vec<CurrencySample>,
+    pub purchases: Vec<PurchaseRecord>,
+}
+
+/// Runs the economy with a fixed timestep and no entities, producing like the
+/// game does and clicking the portal at a steady rate.
+pub struct Simulation {
+    pub data: GameData,
+    pub seconds: f64,
+    /// Steps taken, so `seconds` does not drift from summing timesteps.
+    steps: u64,
+    settings: SimulationSettings,
+    production: ProductionTicks,
+    clicks: f64,
+    /// Seconds since each enabled auto-buyer last tried to buy.
+    since_auto_buy: HashMap<AutomatonVariant, f64>,
+    rng: Rng,
+    next_sample: f64,
+    report: SimulationReport,
+}
+
+impl Simulation {
+    pub fn new(data: GameData, settings: SimulationSettings) -> Self {
+        Self {
+            data,
+            seconds: 0.0,
+            steps: 0,
+            rng: Rng::seeded(settings.seed),
+            settings,
+            production: ProductionTicks::default(),
+            clicks: 0.0,
+            since_auto_buy: HashMap::new(),
+            next_sample: 0.0,
+            report: SimulationReport::default(),
+        }
+    }
+
+    /// Simulates `seconds` of play with `strategy` making the purchases.
+    pub fn run(&mut self, strategy: &mut dyn Strategy, seconds: f64) {
+        let mut pending = None;
+        let end = self.seconds + seconds;
+        while self.seconds < end {
+            self.record_unlocks();
+            self.sample();
+            if pending.is_none() {
+                pending = strategy.next(&self.data);
+            }
+            // Make every purchase that is already affordable before time passes.
+            while let Some(purchase) = &pending {
+                let cost = purchase.cost(&self.data);
+                if !purchase.apply(&mut self.data) {
+                    break;
+                }
+                self.report.purchases.push(PurchaseRecord {
+                    seconds: self.seconds,
+                    purchase: purchase.clone(),
+                    cost: cost.unwrap_or(Currency::ZERO),
+                });
+                pending = strategy.next(&self.data);
+            }
+            self.step();
+        }
+        self.record_unlocks();
+    }
+
+    /// Advances the economy by one timestep.
+    pub fn step(&mut self) {
+        let timestep = self.settings.timestep;
+        self.production.advance(&mut self.data, timestep);
+
+        self.clicks += self.settings.clicks_per_second * timestep;
+        while self.clicks >= 1.0 {
+            self.clicks -= 1.0;
+            self.data.click_portal(self.rng.random_range(0.0..1.0));
+        }
+
+        self.data.tick_buffs(timestep as f32);
+        self.run_auto_buyers(timestep);
+        // Nothing listens for events here; drop them so they don't pile up.
+        self.data.take_events();
+        self.steps += 1;
+        self.seconds = self.steps as f64 * timestep;
+    }
+
+    /// Gives each enabled auto-buyer a purchase attempt every `interval`
+    /// seconds, as the game does.
+    fn run_auto_buyers(&mut self, timestep: f64) {
+        let definitions = self.data.definitions().clone();
+        for definition in definitions.automatons() {
+            let variant = definition.variant;
+            let auto_buyer = self.data.auto_buyer(variant);
+            if !auto_buyer.enabled || !self.data.auto_buyer_unlocked(variant) {
+                self.since_auto_buy.remove(&variant);
+                continue;
+            }
+            let elapsed = self.since_auto_buy.entry(variant).or_default();
+            *elapsed += timestep;
+            if *elapsed >= auto_buyer.interval as f64 {
+                *elapsed = 0.0;
+                self.data.auto_buy(variant);
+            }
+        }
+    }
+
+    fn record_unlocks(&mut self) {
+        for definition in self.data.definitions().automatons() {
+            let variant = definition.variant;
+            let recorded = self.report.unlocks.iter().any(|u| u.variant == variant);
+            if !recorded && self.data.prerequisites_met(variant) {
+                self.report.unlocks.push(UnlockTime {
+                    variant,
+                    seconds: self.seconds,
+                });
+            }
+        }
+    }
+
+    fn sample(&mut self) {
+        if self.seconds < self.next_sample {
+            return;
+        }
+        self.next_sample += self.settings.sample_interval;
+        self.report.currency.push(CurrencySample {
+            seconds: self.seconds,
+            currency: self.data.get_currency(),
+            generated: self.data.total_generated(),
+            rate_per_second: self.data.total_rate_per_second(),
+        });
+    }
+
+    pub fn report(&self) -> &SimulationReport {
+        &self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::AutoBuyer;
+
+    fn hour_of(strategy: &mut dyn Strategy) -> Simulation {
+        let mut simulation = Simulation::new(GameData::default(), SimulationSettings::default());
+        simulation.run(strategy, 60.0 * 60.0);
+        simulation
+    }
+
+    #[test]
+    fn strategies_grow_the_economy_without_entities() {
+        for strategy in [&mut GreedyCheapest as &mut dyn Strategy, &mut BestPayback] {
+            let simulation = hour_of(strategy);
+            let report = simulation.report();
+
+            assert!(report.purchases.len() > 10);
+            assert!(simulation.data.total_rate_per_second() > 0.0);
+            assert!(report.unlocks.len() >= 2);
+            assert_eq!(report.currency.len(), 60);
+        }
+    }
+
+    #[test]
+    fn scripts_wait_for_each_purchase_in_order() {
+        let mut script = Scripted::new(vec![
+            Purchase::Upgrade("focused_gaze".into()),
+            Purchase::Source(AutomatonVariant::Hellmite),
+        ]);
+        let simulation = hour_of(&mut script);
+        let purchases: Vec<_> = simulation
+            .report()
+            .purchases
+            .iter()
+            .map(|record| record.purchase.clone())
+            .collect();
+
+        assert_eq!(
+            purchases,
+            [
+                Purchase::Upgrade("focused_gaze".into()),
+                Purchase::Source(AutomatonVariant::Hellmite)
+            ]
+        );
+        assert_eq!(simulation.report().purchases[0].cost, Currency::new(200));
+    }
+
+    #[test]
+    fn auto_buyers_buy_on_their_interval() {
+        let hellmite = AutomatonVariant::Hellmite;
+        let mut data = GameData::with_hellmites(1);
+        assert!(data.purchase_upgrade("barbed_claws"));
+        assert!(data.purchase_upgrade("restless_instinct"));
+        data.set_auto_buyer(
+            hellmite,
+            AutoBuyer {
+                enabled: true,
+                interval: 1.0,
+                ..Default::default()
+            },
+        );
+        let settings = SimulationSettings {
+            timestep: 0.25,
+            clicks_per_second: 0.0,
+            ..Default::default()
+        };
+        let mut simulation = Simulation::new(data, settings);
+
+        for _ in 0..7 {
+            simulation.step();
+        }
+        assert_eq!(simulation.data.get_quantity_owned_by_source(hellmite), 2);
+        simulation.step();
+        assert_eq!(simulation.data.get_quantity_owned_by_source(hellmite), 3);
+    }
+}
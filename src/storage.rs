@@ -0,0 +1,314 @@
+use bevy::prelude::*;
+use std::io;
+
+/// A save location plus its rotating backups. On native `name` is a file path
+/// and backups are siblings named `<name>.1`, `<name>.2`, …; on the web the same
+/// names are used as localStorage keys.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct SaveStore {
+    name: String,
+}
+
+impl Default for SaveStore {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        {
+            Self::new("game_data")
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            Self::new("save_data.json")
+        }
+    }
+}
+
+impl SaveStore {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of a save generation: 0 is the live save, 1 the newest backup.
+    fn generation_name(&self, generation: usize) -> String {
+        if generation == 0 {
+            self.name.clone()
+        } else {
+            format!("{}.{generation}", self.name)
+        }
+    }
+
+    /// Reads a save generation, with `Ok(None)` meaning it does not exist.
+    pub fn read(&self, generation: usize) -> io::Result<Option<String>> {
+        platform::read(&self.generation_name(generation))
+    }
+
+    /// Moves the live save aside to a timestamped `corrupt` name so it can be
+    /// inspected later instead of being overwritten, returning the new name.
+    pub fn quarantine(&self, timestamp: u64) -> io::Result<String> {
+        let quarantined = self.tagged_name("corrupt", timestamp);
+        platform::rename(&self.name, &quarantined)?;
+        Ok(quarantined)
+    }
+
+    /// Copies every generation to a store under a timestamped `kept` name, so
+    /// backups survive later writes rotating them out. Returns the copy.
+    pub fn keep_copy(&self, timestamp: u64) -> io::Result<SaveStore> {
+        let kept = SaveStore::new(self.tagged_name("kept", timestamp));
+        self.copy_to(&kept)?;
+        Ok(kept)
+    }
+
+    /// The save name with `.<tag>-<timestamp>` inserted before any `.json`.
+    fn tagged_name(&self, tag: &str, timestamp: u64) -> String {
+        match self.name.strip_suffix(".json") {
+            Some(stem) => format!("{stem}.{tag}-{timestamp}.json"),
+            None => format!("{}.{tag}-{timestamp}", self.name),
+        }
+    }
+
+    /// Replaces the live save without ever leaving it half-written: the new
+    /// contents are staged and flushed first, the previous generations shift
+    /// down by one, and the staged copy is renamed into place.
+    pub fn write(&self, contents: &str, backups: usize) -> io::Result<()> {
+        let staged = format!("{}.tmp", self.name);
+        platform::write(&staged, contents)?;
+        self.rotate(backups)?;
+        platform::rename(&staged, &self.name)
+    }
+
+    /// Copies the live save and every backup to `target`.
+    pub fn copy_to(&self, target: &SaveStore) -> io::Result<()> {
+        for generation in self.generations() {
+            if let Some(contents) = self.read(generation)? {
+                platform::write(&target.generation_name(generation), &contents)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes the live save and every backup.
+    pub fn remove(&self) -> io::Result<()> {
+        for generation in self.generations() {
+            platform::remove(&self.generation_name(generation))?;
+        }
+        Ok(())
+    }
+
+    /// Generations that currently exist, live save first. The live save may be
+    /// missing while backups remain.
+    fn generations(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..)
+            .take_while(|&generation| {
+                generation == 0 || platform::exists(&self.generation_name(generation))
+            })
+            .filter(|&generation| platform::exists(&self.generation_name(generation)))
+    }
+
+    fn rotate(&self, backups: usize) -> io::Result<()> {
+        if backups == 0 {
+            return Ok(());
+        }
+        for generation in (1..backups).rev() {
+            let from = self.generation_name(generation);
+            if platform::exists(&from) {
+                platform::rename(&from, &self.generation_name(generation + 1))?;
+            }
+        }
+        if platform::exists(&self.name) {
+            platform::rename(&self.name, &self.generation_name(1))?;
+        }
+        Ok(())
+    }
+}
+
+/// Seconds since the Unix epoch according to the wall clock.
+pub fn unix_time() -> u64 {
+    platform::unix_time()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::{fs, io, io::Write, path::Path, time::SystemTime};
+
+    pub fn unix_time() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0)
+    }
+
+    pub fn read(name: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(name) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn exists(name: &str) -> bool {
+        Path::new(name).exists()
+    }
+
+    pub fn write(name: &str, contents: &str) -> io::Result<()> {
+        let mut file = fs::File::create(name)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    }
+
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        fs::remove_file(name)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use std::io;
+
+    fn storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::other("localStorage is unavailable"))
+    }
+
+    pub fn unix_time() -> u64 {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+
+    pub fn read(name: &str) -> io::Result<Option<String>> {
+        storage()?
+            .get_item(name)
+            .map_err(|err| io::Error::other(format!("{err:?}")))
+    }
+
+    pub fn exists(name: &str) -> bool {
+        matches!(read(name), Ok(Some(_)))
+    }
+
+    pub fn write(name: &str, contents: &str) -> io::Result<()> {
+        storage()?
+            .set_item(name, contents)
+            .map_err(|err| io::Error::other(format!("{err:?}")))
+    }
+
+    pub fn rename(from: &str, to: &str) -> io::Result<()> {
+        let contents = read(from)?.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        write(to, &contents)?;
+        remove(from)
+    }
+
+    pub fn remove(name: &str) -> io::Result<()> {
+        storage()?
+            .remove_item(name)
+            .map_err(|err| io::Error::other(format!("{err:?}")))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A store under a fresh scratch directory, removed again on drop.
+    pub struct ScratchStore {
+        pub store: SaveStore,
+        dir: std::path::PathBuf,
+    }
+
+    impl ScratchStore {
+        pub fn new(test_name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("fever-dream-{test_name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let store = SaveStore::new(dir.join("save_data.json").to_string_lossy());
+            Self { store, dir }
+        }
+
+        /// The scratch directory as a name prefix, with a trailing separator.
+        pub fn root(&self) -> String {
+            format!("{}{}", self.dir.display(), std::path::MAIN_SEPARATOR)
+        }
+    }
+
+    impl Drop for ScratchStore {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn writes_rotate_previous_saves_into_backups() {
+        let scratch = ScratchStore::new("rotate");
+        let store = &scratch.store;
+
+        for contents in ["first", "second", "third", "fourth"] {
+            store.write(contents, 2).unwrap();
+        }
+
+        assert_eq!(store.read(0).unwrap().as_deref(), Some("fourth"));
+        assert_eq!(store.read(1).unwrap().as_deref(), Some("third"));
+        assert_eq!(store.read(2).unwrap().as_deref(), Some("second"));
+        assert_eq!(store.read(3).unwrap(), None);
+        assert!(!platform::exists(&format!("{}.tmp", store.name)));
+    }
+
+    #[test]
+    fn copies_and_removals_cover_every_generation() {
+        let scratch = ScratchStore::new("copy-remove");
+        let store = &scratch.store;
+        for contents in ["first", "second", "third"] {
+            store.write(contents, 3).unwrap();
+        }
+        let copy = SaveStore::new(format!("{}copy.json", scratch.root()));
+
+        store.copy_to(&copy).unwrap();
+        store.remove().unwrap();
+
+        assert_eq!(store.read(0).unwrap(), None);
+        assert_eq!(store.read(2).unwrap(), None);
+        assert_eq!(copy.read(0).unwrap().as_deref(), Some("third"));
+        assert_eq!(copy.read(2).unwrap().as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn quarantine_moves_the_live_save_to_a_timestamped_name() {
+        let scratch = ScratchStore::new("quarantine");
+        let store = &scratch.store;
+        store.write("damaged", 0).unwrap();
+
+        let quarantined = store.quarantine(1_700_000_000).unwrap();
+
+        assert!(quarantined.ends_with("save_data.corrupt-1700000000.json"));
+        assert_eq!(
+            platform::read(&quarantined).unwrap().as_deref(),
+            Some("damaged")
+        );
+        assert_eq!(store.read(0).unwrap(), None);
+    }
+
+    #[test]
+    fn kept_copies_survive_later_rotation() {
+        let scratch = ScratchStore::new("keep-copy");
+        let store = &scratch.store;
+        for contents in ["first", "second"] {
+            store.write(contents, 1).unwrap();
+        }
+        store.quarantine(1_700_000_000).unwrap();
+
+        let kept = store.keep_copy(1_700_000_001).unwrap();
+        for contents in ["fresh", "fresher"] {
+            store.write(contents, 1).unwrap();
+        }
+
+        assert!(kept.name().ends_with("save_data.kept-1700000001.json"));
+        assert_eq!(kept.read(0).unwrap(), None);
+        assert_eq!(kept.read(1).unwrap().as_deref(), Some("first"));
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+
+use crate::{
+    data::{AutomatonVariant, GameData},
+    definitions::{DefinitionFile, Definitions},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum SynergyEffect {
+    /// Adds this fraction of the target's output per step.
+    ProductionBonus(f64),
+    /// Shortens the target's cooldown by this fraction per step, compounding.
+    CooldownReduction(f32),
+}
+
+/// How the source's owned count turns into steps of the effect.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum SynergyScaling {
+    /// One step per source owned.
+    PerOwned,
+    /// One step once this many are owned.
+    Threshold(u64),
+    /// One step per full group of this many.
+    PerGroup(u64),
+}
+
+/// One entry of `assets/data/synergies.json`: owning `source` improves `target`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub struct Synergy {
+    pub source: AutomatonVariant,
+    pub target: AutomatonVariant,
+    pub effect: SynergyEffect,
+    pub scaling: SynergyScaling,
+}
+
+impl Synergy {
+    pub fn steps(&self, game_data: &GameData) -> u64 {
+        let owned = game_data.get_quantity_owned_by_source(self.source);
+        match self.scaling {
+            SynergyScaling::PerOwned => owned,
+            SynergyScaling::Threshold(quantity) => (owned >= quantity) as u64,
+            SynergyScaling::PerGroup(size) => owned / size,
+        }
+    }
+
+    /// Output multiplier after `steps`; 1 for cooldown synergies.
+    pub fn production_multiplier(&self, steps: u64) -> f64 {
+        match self.effect {
+            SynergyEffect::ProductionBonus(fraction) => 1.0 + fraction * steps as f64,
+            SynergyEffect::CooldownReduction(_) => 1.0,
+        }
+    }
+
+    /// Cooldown multiplier after `steps`; 1 for production synergies.
+    pub fn cooldown_multiplier(&self, steps: u64) -> f32 {
+        match self.effect {
+            SynergyEffect::CooldownReduction(fraction) => (1.0 - fraction).powf(steps as f32),
+            SynergyEffect::ProductionBonus(_) => 1.0,
+        }
+    }
+
+    /// Describes the current bonus for the variant panel, e.g.
+    /// "5 Lorgners: +10% output".
+    pub fn describe(&self, steps: u64, game_data: &GameData) -> String {
+        let owned = game_data.get_quantity_owned_by_source(self.source);
+        let effect = match self.effect {
+            SynergyEffect::ProductionBonus(_) => format!(
+                "+{:.0}% output",
+                (self.production_multiplier(steps) - 1.0) * 100.0
+            ),
+            SynergyEffect::CooldownReduction(_) => format!(
+                "-{:.0}% cooldown",
+                (1.0 - self.cooldown_multiplier(steps)) * 100.0
+            ),
+        };
+        format!(
+            "{} {}: {}",
+            owned,
+            game_data
+                .definitions()
+                .source(self.source)
+                .label_for_quantity(owned),
+            effect
+        )
+    }
+}
+
+#[derive(Asset, TypePath, Debug, Clone, serde::Deserialize)]
+pub struct SynergyDefinitions {
+    pub synergies: Vec<Synergy>,
+}
+
+/// Synergies with at least one step that apply to `target`.
+pub fn active_synergies(
+    game_data: &GameData,
+    target: AutomatonVariant,
+) -> impl Iterator<Item = (&Synergy, u64)> {
+    game_data
+        .definitions()
+        .synergies
+        .iter()
+        .filter(move |synergy| synergy.target == target)
+        .map(|synergy| (synergy, synergy.steps(game_data)))
+        .filter(|(_, steps)| *steps > 0)
+}
+
+impl DefinitionFile for SynergyDefinitions {
+    const PATH: &'static str = "data/synergies.json";
+    const BUILTIN: &'static str = include_str!("../assets/data/synergies.json");
+
+    /// Checks that sources are automatons and that effects and scaling are in range.
+    fn validate(&self, _asset_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        for synergy in &self.synergies {
+            let name = format!("the {:?} -> {:?} synergy", synergy.source, synergy.target);
+            if synergy.source == AutomatonVariant::Portal {
+                return Err(format!("{name} has the portal as its source"));
+            }
+            let effect_ok = match synergy.effect {
+                SynergyEffect::ProductionBonus(fraction) => fraction > 0.0,
+                SynergyEffect::CooldownReduction(fraction) => fraction > 0.0 && fraction < 1.0,
+            };
+            let scaling_ok = match synergy.scaling {
+                SynergyScaling::PerOwned => true,
+                SynergyScaling::Threshold(quantity) => quantity > 0,
+                SynergyScaling::PerGroup(size) => size > 0,
+            };
+            if !effect_ok || !scaling_ok {
+                return Err(format!("{name} is out of range"));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&self, definitions: &mut Definitions) {
+        definitions.synergies = self.synergies.as_slice().into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions;
+
+    #[test]
+    fn builtin_synergies_are_valid() {
+        let definitions: SynergyDefinitions =
+            definitions::parse(SynergyDefinitions::BUILTIN, &|_| true).unwrap();
+
+        assert!(!definitions.synergies.is_empty());
+    }
+
+    #[test]
+    fn groups_of_zero_are_rejected() {
+        let result = definitions::parse_edited::<SynergyDefinitions>(|synergies| {
+            synergies[0]["scaling"] = serde_json::json!({ "PerGroup": 0 });
+        });
+
+        assert!(result.is_err());
+    }
+}
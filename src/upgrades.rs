@@ -0,0 +1,470 @@
+use bevy::{picking::hover::Hovered, prelude::*, ui_widgets::observe};
+use std::collections::HashSet;
+
+use crate::{
+    AppState,
+    currency::Currency,
+    data::{AutomatonVariant, GameData},
+    definitions::{DefinitionFile, Definitions, DefinitionsChanged},
+    interface::{
+        CONTROL_ACCENT, CONTROL_ACCENT_HOVERED, CONTROL_MUTED_TEXT, CONTROL_TEXT,
+        CONTROL_TEXT_FONT_SIZE, CONTROL_TITLE_FONT_SIZE, CONTROL_TRACK, FONT_PATH, HudMenu,
+        HudPanel, PANEL_BACKGROUND, PANEL_BORDER, control_text, dialog_button, toggle_hud_panel,
+    },
+};
+
+const CARD_FONT_SIZE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum UpgradeEffect {
+    /// Multiplies one source's production.
+    VariantMultiplier {
+        variant: AutomatonVariant,
+        multiplier: f64,
+    },
+    /// Multiplies every source's production.
+    GlobalMultiplier { multiplier: f64 },
+    /// Shortens cooldowns by a fraction, for one variant or all of them.
+    CooldownReduction {
+        #[serde(default)]
+        variant: Option<AutomatonVariant>,
+        fraction: f32,
+    },
+    /// Adds base Entropy to every portal click.
+    PortalClickPower { amount: Currency },
+    /// Adds a fraction of the automatons' Entropy/s to every portal click.
+    PortalRateShare { fraction: f64 },
+    /// Raises the chance of a critical portal click.
+    PortalCritChance { chance: f64 },
+    /// Shrinks how much each purchase raises the next price, by a fraction of
+    /// `ratio - 1`, for one variant or all of them.
+    CostRatioDiscount {
+        #[serde(default)]
+        variant: Option<AutomatonVariant>,
+        fraction: f64,
+    },
+    /// Unlocks the auto-buyer for one variant or all of them.
+    AutoBuyer {
+        #[serde(default)]
+        variant: Option<AutomatonVariant>,
+    },
+}
+
+impl UpgradeEffect {
+    pub fn describe(&self, definitions: &Definitions) -> String {
+        let subject = |variant: Option<AutomatonVariant>| match variant {
+            Some(variant) => definitions.source(variant).plural_display_name.clone(),
+            None => "All automatons".to_string(),
+        };
+        match *self {
+            UpgradeEffect::VariantMultiplier {
+                variant,
+                multiplier,
+            } => format!(
+                "{} produce x{multiplier}",
+                definitions.source(variant).plural_display_name
+            ),
+            UpgradeEffect::GlobalMultiplier { multiplier } => {
+                format!("All production x{multiplier}")
+            }
+            UpgradeEffect::CooldownReduction { variant, fraction } => format!(
+                "{}: {:.0}% shorter cooldowns",
+                subject(variant),
+                fraction * 100.0
+            ),
+            UpgradeEffect::PortalClickPower { amount } => {
+                format!("Portal clicks +{amount} Entropy")
+            }
+            UpgradeEffect::PortalRateShare { fraction } => {
+                format!("Portal clicks +{:.0}% of Entropy/s", fraction * 100.0)
+            }
+            UpgradeEffect::PortalCritChance { chance } => {
+                format!("+{:.0}% portal crit chance", chance * 100.0)
+            }
+            UpgradeEffect::CostRatioDiscount { variant, fraction } => format!(
+                "{}: prices rise {:.0}% slower",
+                subject(variant),
+                fraction * 100.0
+            ),
+            UpgradeEffect::AutoBuyer { variant } => {
+                format!("{}: unlocks the auto-buyer", subject(variant))
+            }
+        }
+    }
+}
+
+/// One entry of `assets/data/upgrades.json`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct UpgradeDefinition {
+    pub id: String,
+    pub name: String,
+    pub cost: Currency,
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    pub effect: UpgradeEffect,
+}
+
+#[derive(Asset, TypePath, Debug, Clone, serde::Deserialize)]
+pub struct UpgradeDefinitions {
+    pub upgrades: Vec<UpgradeDefinition>,
+}
+
+impl DefinitionFile for UpgradeDefinitions {
+    const PATH: &'static str = "data/upgrades.json";
+    const BUILTIN: &'static str = include_str!("../assets/data/upgrades.json");
+
+    /// Checks that ids are unique, prerequisites exist and never loop, and
+    /// effects are in range.
+    fn validate(&self, _asset_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for upgrade in &self.upgrades {
+            if !ids.insert(upgrade.id.as_str()) {
+                return Err(format!("upgrade {} is defined more than once", upgrade.id));
+            }
+        }
+
+        for upgrade in &self.upgrades {
+            if let Some(missing) = upgrade
+                .prerequisites
+                .iter()
+                .find(|id| !ids.contains(id.as_str()))
+            {
+                return Err(format!("upgrade {} requires unknown {missing}", upgrade.id));
+            }
+            let in_range = match upgrade.effect {
+                UpgradeEffect::VariantMultiplier { multiplier, .. }
+                | UpgradeEffect::GlobalMultiplier { multiplier } => multiplier > 0.0,
+                UpgradeEffect::CooldownReduction { fraction, .. } => (0.0..1.0).contains(&fraction),
+                UpgradeEffect::CostRatioDiscount { fraction, .. } => {
+                    (0.0..=1.0).contains(&fraction)
+                }
+                UpgradeEffect::PortalClickPower { .. } | UpgradeEffect::AutoBuyer { .. } => true,
+                UpgradeEffect::PortalRateShare { fraction } => fraction > 0.0,
+                UpgradeEffect::PortalCritChance { chance } => chance > 0.0 && chance <= 1.0,
+            };
+            if !in_range {
+                return Err(format!("upgrade {} has an out of range effect", upgrade.id));
+            }
+        }
+
+        if self.tiers().is_none() {
+            return Err("upgrade prerequisites loop".to_string());
+        }
+        Ok(())
+    }
+
+    fn apply(&self, definitions: &mut Definitions) {
+        definitions.upgrades = self.upgrades.as_slice().into();
+    }
+}
+
+impl UpgradeDefinitions {
+    /// Each upgrade's depth in the tree: 0 without prerequisites, otherwise
+    /// one more than its deepest prerequisite. `None` if prerequisites loop.
+    fn tiers(&self) -> Option<Vec<usize>> {
+        tiers(&self.upgrades)
+    }
+}
+
+fn tiers(upgrades: &[UpgradeDefinition]) -> Option<Vec<usize>> {
+    let mut tiers: Vec<Option<usize>> = vec![None; upgrades.len()];
+    // Each pass settles at least one more upgrade unless there is a loop.
+    for _ in 0..upgrades.len() {
+        for (index, upgrade) in upgrades.iter().enumerate() {
+            let prerequisite_tiers: Option<Vec<usize>> = upgrade
+                .prerequisites
+                .iter()
+                .map(|id| {
+                    upgrades
+                        .iter()
+                        .position(|other| &other.id == id)
+                        .and_then(|position| tiers[position])
+                })
+                .collect();
+            if let Some(prerequisite_tiers) = prerequisite_tiers {
+                tiers[index] = Some(prerequisite_tiers.into_iter().max().map_or(0, |t| t + 1));
+            }
+        }
+    }
+    tiers.into_iter().collect()
+}
+
+pub struct UpgradesPlugin;
+
+impl Plugin for UpgradesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            spawn_upgrade_panel.after(crate::interface::setup),
+        );
+        app.add_systems(
+            Update,
+            (rebuild_upgrade_tree, update_upgrade_cards)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+struct UpgradePanel;
+
+#[derive(Component)]
+struct UpgradeTree;
+
+#[derive(Component)]
+struct UpgradeCard(UpgradeDefinition);
+
+#[derive(Component)]
+struct UpgradeCardState;
+
+fn spawn_upgrade_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    menu: Single<Entity, With<HudMenu>>,
+) {
+    let font_handle = asset_server.load(FONT_PATH);
+
+    commands.entity(*menu).with_child(dialog_button(
+        &font_handle,
+        "Upgrades",
+        observe(toggle_hud_panel::<UpgradePanel>),
+    ));
+
+    commands
+        .spawn((
+            UpgradePanel,
+            HudPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                right: px(12),
+                top: px(56),
+                width: px(540),
+                padding: UiRect::all(px(14)),
+                border: UiRect::all(px(1)),
+                border_radius: BorderRadius::all(px(6)),
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                row_gap: px(10),
+                ..default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            BorderColor::all(PANEL_BORDER),
+            GlobalZIndex(5),
+        ))
+        .with_children(|panel| {
+            panel.spawn(control_text(
+                &font_handle,
+                "Upgrades",
+                CONTROL_TITLE_FONT_SIZE,
+                CONTROL_TEXT,
+            ));
+            panel.spawn((
+                UpgradeTree,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: px(8),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Lays the tree out as one row per tier, rebuilt when the definitions change.
+fn rebuild_upgrade_tree(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    definitions: Res<Definitions>,
+    mut changes: MessageReader<DefinitionsChanged<UpgradeDefinitions>>,
+    trees: Query<Entity, With<UpgradeTree>>,
+    spawned: Query<(), Added<UpgradeTree>>,
+) {
+    if changes.read().count() == 0 && spawned.is_empty() {
+        return;
+    }
+
+    let font_handle = asset_server.load(FONT_PATH);
+    let upgrades = &definitions.upgrades;
+    let tiers = tiers(upgrades).unwrap_or_default();
+    let tier_count = tiers.iter().max().map_or(0, |tier| tier + 1);
+    for tree in &trees {
+        commands.entity(tree).despawn_children();
+        commands.entity(tree).with_children(|tree| {
+            for tier in 0..tier_count {
+                tree.spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: px(8),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (upgrade, _) in upgrades
+                        .iter()
+                        .zip(&tiers)
+                        .filter(|(_, upgrade_tier)| **upgrade_tier == tier)
+                    {
+                        spawn_upgrade_card(row, &font_handle, &definitions, upgrade);
+                    }
+                });
+            }
+        });
+    }
+}
+
+fn spawn_upgrade_card(
+    row: &mut ChildSpawnerCommands,
+    font_handle: &Handle<Font>,
+    definitions: &Definitions,
+    upgrade: &UpgradeDefinition,
+) {
+    row.spawn((
+        UpgradeCard(upgrade.clone()),
+        Node {
+            width: px(164),
+            padding: UiRect::all(px(6)),
+            border: UiRect::all(px(1)),
+            border_radius: BorderRadius::all(px(4)),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(2),
+            ..default()
+        },
+        BackgroundColor(PANEL_BACKGROUND),
+        BorderColor::all(PANEL_BORDER),
+        Hovered::default(),
+        observe(on_upgrade_card),
+    ))
+    .with_children(|card| {
+        card.spawn((
+            control_text(
+                font_handle,
+                upgrade.name.clone(),
+                CONTROL_TEXT_FONT_SIZE,
+                CONTROL_TEXT,
+            ),
+            Pickable::IGNORE,
+        ));
+        card.spawn((
+            control_text(
+                font_handle,
+                upgrade.effect.describe(definitions),
+                CARD_FONT_SIZE,
+                CONTROL_MUTED_TEXT,
+            ),
+            Pickable::IGNORE,
+        ));
+        card.spawn((
+            UpgradeCardState,
+            control_text(font_handle, "", CARD_FONT_SIZE, CONTROL_MUTED_TEXT),
+            Pickable::IGNORE,
+        ));
+    });
+}
+
+type UpgradeCardQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static UpgradeCard,
+        &'static Hovered,
+        &'static mut BackgroundColor,
+        &'static mut BorderColor,
+        &'static Children,
+    ),
+>;
+
+fn update_upgrade_cards(
+    data: Res<GameData>,
+    panels: Query<&Node, With<UpgradePanel>>,
+    mut cards: UpgradeCardQuery<'_, '_>,
+    mut states: Query<(&mut Text, &mut TextColor), With<UpgradeCardState>>,
+) {
+    if panels.iter().all(|node| node.display == Display::None) {
+        return;
+    }
+    for (card, hovered, mut bg, mut border, children) in &mut cards {
+        let upgrade = &card.0;
+        let owned = data.has_upgrade(&upgrade.id);
+        let unlocked = data.upgrade_unlocked(upgrade);
+        let affordable = data.can_purchase_upgrade(upgrade);
+
+        let (state, color) = if owned {
+            ("Owned".to_string(), CONTROL_ACCENT_HOVERED)
+        } else if !unlocked {
+            let missing: Vec<&str> = upgrade
+                .prerequisites
+                .iter()
+                .filter(|id| !data.has_upgrade(id))
+                .filter_map(|id| data.definitions().upgrade(id))
+                .map(|u| u.name.as_str())
+                .collect();
+            (
+                format!("Requires {}", missing.join(", ")),
+                CONTROL_MUTED_TEXT,
+            )
+        } else if affordable {
+            (format!("Buy: {} Entropy", upgrade.cost), CONTROL_TEXT)
+        } else {
+            (
+                format!("Cost: {} Entropy", upgrade.cost),
+                CONTROL_MUTED_TEXT,
+            )
+        };
+
+        bg.0 = if owned || (affordable && hovered.get()) {
+            CONTROL_TRACK
+        } else {
+            PANEL_BACKGROUND
+        };
+        border.set_all(if owned || (affordable && hovered.get()) {
+            CONTROL_ACCENT_HOVERED
+        } else if affordable {
+            CONTROL_ACCENT
+        } else {
+            PANEL_BORDER
+        });
+        for child in children.iter() {
+            if let Ok((mut text, mut text_color)) = states.get_mut(child) {
+                if text.0 != state {
+                    text.0 = state.clone();
+                }
+                text_color.0 = color;
+            }
+        }
+    }
+}
+
+fn on_upgrade_card(on: On<Pointer<Click>>, cards: Query<&UpgradeCard>, mut data: ResMut<GameData>) {
+    if on.button != PointerButton::Primary {
+        return;
+    }
+    if let Ok(card) = cards.get(on.event_target()) {
+        data.purchase_upgrade(&card.0.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions;
+
+    #[test]
+    fn builtin_upgrades_form_a_tree() {
+        let definitions = definitions::parse_edited::<UpgradeDefinitions>(|_| {}).unwrap();
+        let tiers = definitions.tiers().unwrap();
+
+        assert_eq!(tiers[0], 0);
+        assert!(tiers.iter().any(|&tier| tier >= 3));
+    }
+
+    #[test]
+    fn looping_or_unknown_prerequisites_are_rejected() {
+        let looping = definitions::parse_edited::<UpgradeDefinitions>(|upgrades| {
+            upgrades[0]["prerequisites"] = serde_json::json!(["hardened_chitin"]);
+        });
+        let unknown = definitions::parse_edited::<UpgradeDefinitions>(|upgrades| {
+            upgrades[0]["prerequisites"] = serde_json::json!(["not_an_upgrade"]);
+        });
+
+        assert!(looping.is_err());
+        assert!(unknown.is_err());
+    }
+}